@@ -0,0 +1,695 @@
+use crate::{Chapter, FSPoint, FScript};
+
+/// intensity label of a [`Section`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SectionLabel {
+    Pause,
+    Slow,
+    Medium,
+    Fast,
+}
+
+impl SectionLabel {
+    /// lowercase name used in chapter names and subtitles
+    pub fn name(&self) -> &'static str {
+        match self {
+            SectionLabel::Pause => "pause",
+            SectionLabel::Slow => "slow",
+            SectionLabel::Medium => "medium",
+            SectionLabel::Fast => "fast",
+        }
+    }
+}
+
+/// speed thresholds in units per second used by [`classify_sections`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// below this speed a window is a pause
+    pub pause: f64,
+    /// below this speed a window is slow
+    pub slow: f64,
+    /// below this speed a window is medium, anything faster is fast
+    pub medium: f64,
+    /// length of the rolling window the speed is averaged over
+    pub window_ms: i64,
+    /// sections shorter than this are merged into a neighbour
+    pub min_section_ms: i64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            pause: 10.0,
+            slow: 100.0,
+            medium: 250.0,
+            window_ms: 1000,
+            min_section_ms: 2000,
+        }
+    }
+}
+
+impl Thresholds {
+    pub(crate) fn label(&self, speed: f64) -> SectionLabel {
+        if speed < self.pause {
+            SectionLabel::Pause
+        } else if speed < self.slow {
+            SectionLabel::Slow
+        } else if speed < self.medium {
+            SectionLabel::Medium
+        } else {
+            SectionLabel::Fast
+        }
+    }
+}
+
+/// a labeled stretch of a script
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Section {
+    pub start: i64,
+    pub end: i64,
+    pub label: SectionLabel,
+    /// average speed over the section in units per second
+    pub average_speed: f64,
+}
+
+/// splits a script into pause/slow/medium/fast regions
+/// each segment is labeled by the rolling speed over `window_ms` around its middle,
+/// adjacent segments with the same label are merged and sections shorter than
+/// `min_section_ms` are absorbed by their neighbours
+pub fn classify_sections(script: &FScript, thresholds: Thresholds) -> Vec<Section> {
+    let actions = &script.actions;
+    let (Some(first), Some(last)) = (actions.first(), actions.last()) else {
+        return Vec::new();
+    };
+    let half = thresholds.window_ms.max(1) / 2;
+    let (start, end) = (first.at as i64, last.at as i64);
+
+    // distance travelled up to each action, so a window is two lookups
+    let mut travelled = Vec::with_capacity(actions.len());
+    travelled.push(0.0);
+    for pair in actions.windows(2) {
+        let step = (pair[1].pos - pair[0].pos).abs() as f64;
+        travelled.push(travelled[travelled.len() - 1] + step);
+    }
+    // the window edges only move forward, so each keeps its own segment index
+    let distance_at = |idx: &mut usize, t: i64| {
+        while *idx + 1 < actions.len() && actions[*idx + 1].at as i64 <= t {
+            *idx += 1;
+        }
+        let Some(next) = actions.get(*idx + 1) else {
+            return travelled[*idx];
+        };
+        let (a, b) = (actions[*idx].at as i64, next.at as i64);
+        let step = (next.pos - actions[*idx].pos).abs() as f64;
+        travelled[*idx] + step * (t - a).max(0) as f64 / (b - a) as f64
+    };
+
+    let mut sections: Vec<Section> = Vec::new();
+    let (mut lo, mut hi) = (0, 0);
+    for pair in actions.windows(2) {
+        let (a, b) = (pair[0].at as i64, pair[1].at as i64);
+        if b <= a {
+            continue;
+        }
+        let middle = (a + b) / 2;
+        let (from, to) = ((middle - half).max(start), (middle + half).min(end));
+        let distance = distance_at(&mut hi, to) - distance_at(&mut lo, from);
+        let speed = distance / (to - from).max(1) as f64 * 1000.0;
+        push_merged(&mut sections, a, b, thresholds.label(speed));
+    }
+
+    // absorb short sections into the previous one, or the next if it's first
+    let mut idx = 0;
+    while sections.len() > 1 && idx < sections.len() {
+        let section = sections[idx];
+        if section.end - section.start >= thresholds.min_section_ms {
+            idx += 1;
+            continue;
+        }
+        sections.remove(idx);
+        if idx > 0 {
+            sections[idx - 1].end = section.end;
+            idx -= 1;
+        } else {
+            sections[0].start = section.start;
+        }
+        // the absorbing section may now touch one with the same label
+        if idx + 1 < sections.len() && sections[idx].label == sections[idx + 1].label {
+            sections[idx].end = sections[idx + 1].end;
+            sections.remove(idx + 1);
+        }
+        idx = idx.saturating_sub(1);
+    }
+
+    let (mut lo, mut hi) = (0, 0);
+    for section in &mut sections {
+        let distance = distance_at(&mut hi, section.end) - distance_at(&mut lo, section.start);
+        section.average_speed = distance / (section.end - section.start).max(1) as f64 * 1000.0;
+    }
+    sections
+}
+
+/// how [`auto_chapters`] names the chapters it writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChapterNaming {
+    /// after their intensity, e.g. `fast 2` for the second fast part
+    #[default]
+    Intensity,
+    /// `Scene 1`, `Scene 2` and so on
+    Scene,
+}
+
+/// settings for [`auto_chapters`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoChapterOptions {
+    /// where intensity changes, `min_section_ms` is the shortest chapter written
+    pub thresholds: Thresholds,
+    /// split where the intensity changes
+    pub split_on_intensity: bool,
+    /// split in the middle of every gap at least this long, see [`find_gaps`]
+    pub min_gap_ms: Option<i64>,
+    pub naming: ChapterNaming,
+    /// drop the existing chapters instead of adding to them
+    pub replace_existing: bool,
+}
+
+impl Default for AutoChapterOptions {
+    fn default() -> Self {
+        Self {
+            thresholds: Thresholds {
+                min_section_ms: 30_000,
+                ..Default::default()
+            },
+            split_on_intensity: true,
+            min_gap_ms: None,
+            naming: ChapterNaming::Intensity,
+            replace_existing: false,
+        }
+    }
+}
+
+/// writes chapters into the metadata, split where the intensity changes and in the
+/// middle of long gaps, a boundary that would leave a chapter shorter than
+/// `min_section_ms` is dropped, returns how many chapters were added
+pub fn auto_chapters(script: &mut FScript, options: AutoChapterOptions) -> usize {
+    let (Some(first), Some(last)) = (script.actions.first(), script.actions.last()) else {
+        return 0;
+    };
+    let (start, end) = (first.at as i64, last.at as i64);
+    let sections = classify_sections(script, options.thresholds);
+
+    let mut candidates: Vec<i64> = Vec::new();
+    if options.split_on_intensity {
+        candidates.extend(sections.iter().skip(1).map(|section| section.start));
+    }
+    if let Some(min_gap_ms) = options.min_gap_ms {
+        let gaps = find_gaps(script, min_gap_ms);
+        let inside = gaps
+            .iter()
+            .filter(|gap| gap.start >= start && gap.end <= end);
+        candidates.extend(inside.map(|gap| (gap.start + gap.end) / 2));
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let min = options.thresholds.min_section_ms;
+    let mut bounds = vec![start];
+    for t in candidates {
+        if t - bounds[bounds.len() - 1] >= min {
+            bounds.push(t);
+        }
+    }
+    // a short last chapter joins the one before it
+    if bounds.len() > 1 && end - bounds[bounds.len() - 1] < min {
+        bounds.pop();
+    }
+    bounds.push(end.max(start + 1));
+
+    let mut seen = [0usize; 4];
+    let new: Vec<Chapter> = bounds
+        .windows(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let name = match options.naming {
+                ChapterNaming::Scene => format!("Scene {}", i + 1),
+                ChapterNaming::Intensity => {
+                    let label = dominant_label(&sections, pair[0], pair[1]);
+                    seen[label as usize] += 1;
+                    format!("{} {}", label.name(), seen[label as usize])
+                }
+            };
+            Chapter {
+                name,
+                start_time: pair[0],
+                end_time: pair[1],
+            }
+        })
+        .collect();
+
+    let added = new.len();
+    let chapters = script.metadata.chapters_mut();
+    if options.replace_existing {
+        chapters.clear();
+    }
+    chapters.extend(new);
+    chapters.sort_by_key(|c| c.start_time);
+    added
+}
+
+fn push_merged(sections: &mut Vec<Section>, start: i64, end: i64, label: SectionLabel) {
+    match sections.last_mut() {
+        Some(last) if last.label == label => last.end = end,
+        _ => sections.push(Section {
+            start,
+            end,
+            label,
+            average_speed: 0.0,
+        }),
+    }
+}
+
+/// the label of the section overlapping `start..end` the most
+fn dominant_label(sections: &[Section], start: i64, end: i64) -> SectionLabel {
+    sections
+        .iter()
+        .max_by_key(|section| section.end.min(end) - section.start.max(start))
+        .map_or(SectionLabel::Pause, |section| section.label)
+}
+
+/// one move in a single direction, from a peak to a valley or back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stroke {
+    pub start: i64,
+    pub end: i64,
+    /// position at the start of the stroke
+    pub from: i32,
+    /// position at the end of the stroke
+    pub to: i32,
+}
+
+impl Stroke {
+    /// distance covered, always positive
+    pub fn depth(&self) -> i32 {
+        (self.to - self.from).abs()
+    }
+
+    pub fn duration_ms(&self) -> i64 {
+        self.end - self.start
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.to > self.from
+    }
+}
+
+/// splits a script into strokes at every direction reversal
+/// holds at the same position neither end nor extend a stroke
+pub fn detect_strokes(script: &FScript) -> Vec<Stroke> {
+    strokes_in(&script.actions)
+}
+
+pub(crate) fn strokes_in(actions: &[FSPoint]) -> Vec<Stroke> {
+    let mut strokes: Vec<Stroke> = Vec::new();
+    for pair in actions.windows(2) {
+        let delta = pair[1].pos - pair[0].pos;
+        if delta == 0 {
+            continue;
+        }
+        match strokes.last_mut() {
+            Some(last) if (last.to - last.from).signum() == delta.signum() => {
+                last.end = pair[1].at as i64;
+                last.to = pair[1].pos;
+            }
+            _ => strokes.push(Stroke {
+                start: pair[0].at as i64,
+                end: pair[1].at as i64,
+                from: pair[0].pos,
+                to: pair[1].pos,
+            }),
+        }
+    }
+    strokes
+}
+
+/// strokes per minute over time as `(time_ms, spm)` pairs
+/// each sample counts the strokes ending within `window_ms / 2` of it,
+/// samples are half a window apart starting at the first action
+pub fn tempo_curve(script: &FScript, window_ms: i64) -> Vec<(i64, f64)> {
+    let (Some(first), Some(last)) = (script.actions.first(), script.actions.last()) else {
+        return Vec::new();
+    };
+    if window_ms <= 0 {
+        return Vec::new();
+    }
+    let ends: Vec<i64> = detect_strokes(script).iter().map(|s| s.end).collect();
+    let half = window_ms / 2;
+    let step = half.max(1);
+    let per_minute = 60_000.0 / window_ms as f64;
+
+    let mut curve = Vec::new();
+    let mut t = first.at as i64;
+    while t <= last.at as i64 {
+        let lo = ends.partition_point(|&end| end < t - half);
+        let hi = ends.partition_point(|&end| end < t - half + window_ms);
+        curve.push((t, (hi - lo) as f64 * per_minute));
+        t += step;
+    }
+    curve
+}
+
+/// a stretch of a script without any movement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Gap {
+    pub fn duration_ms(&self) -> i64 {
+        self.end - self.start
+    }
+}
+
+/// finds every stretch of at least `min_gap_ms` without movement
+/// that is consecutive actions holding the same position, the time before the first
+/// action, and the time after the last one when the metadata has a duration;
+/// actions far apart at different positions are a slow move and not a gap
+pub fn find_gaps(script: &FScript, min_gap_ms: i64) -> Vec<Gap> {
+    let mut still: Vec<Gap> = Vec::new();
+    let mut add = |start: i64, end: i64| {
+        if end <= start {
+            return;
+        }
+        match still.last_mut() {
+            Some(gap) if gap.end == start => gap.end = end,
+            _ => still.push(Gap { start, end }),
+        }
+    };
+    let actions = &script.actions;
+    add(0, actions.first().map_or(0, |pt| pt.at as i64));
+    for pair in actions.windows(2) {
+        if pair[0].pos == pair[1].pos {
+            add(pair[0].at as i64, pair[1].at as i64);
+        }
+    }
+    if script.metadata.duration > 0 {
+        let last = actions.last().map_or(0, |pt| pt.at as i64);
+        add(last, script.metadata.duration as i64 * 1000);
+    }
+    still.retain(|gap| gap.duration_ms() >= min_gap_ms.max(1));
+    still
+}
+
+/// speed at which a heatmap bucket is fully saturated, units per second
+pub const HEATMAP_MAX_SPEED: f64 = 400.0;
+
+/// average speed per `bucket_ms` bucket from 0 to the last action, scaled to 0.0-1.0
+/// the scale is fixed at [`HEATMAP_MAX_SPEED`] so heatmaps of different scripts
+/// can be compared, frontends only need to map the values to colors
+pub fn heatmap(script: &FScript, bucket_ms: i64) -> Vec<f32> {
+    let Some(last) = script.actions.last() else {
+        return Vec::new();
+    };
+    if bucket_ms <= 0 {
+        return Vec::new();
+    }
+    let buckets = ((last.at as i64 + bucket_ms - 1) / bucket_ms).max(1) as usize;
+    let mut distance = vec![0.0f64; buckets];
+
+    for pair in script.actions.windows(2) {
+        let (a, b) = (pair[0].at as i64, pair[1].at as i64);
+        if b <= a || b <= 0 {
+            continue;
+        }
+        let per_ms = (pair[1].pos - pair[0].pos).abs() as f64 / (b - a) as f64;
+        // spread the segment's distance over every bucket it overlaps
+        let mut t = a.max(0);
+        while t < b {
+            let idx = (t / bucket_ms) as usize;
+            let bucket_end = (idx as i64 + 1) * bucket_ms;
+            let next = bucket_end.min(b);
+            distance[idx.min(buckets - 1)] += per_ms * (next - t) as f64;
+            t = next;
+        }
+    }
+
+    distance
+        .into_iter()
+        .map(|d| {
+            let speed = d / bucket_ms as f64 * 1000.0;
+            (speed / HEATMAP_MAX_SPEED).clamp(0.0, 1.0) as f32
+        })
+        .collect()
+}
+
+/// average speed in units per second between two times
+/// segments only partly inside the range contribute proportionally
+pub(crate) fn speed_between(actions: &[FSPoint], start: i64, end: i64) -> f64 {
+    if end <= start {
+        return 0.0;
+    }
+    let mut distance = 0.0;
+    for pair in actions.windows(2) {
+        let (a, b) = (pair[0].at as i64, pair[1].at as i64);
+        if b <= start || a >= end || b <= a {
+            continue;
+        }
+        let overlap = (b.min(end) - a.max(start)) as f64;
+        distance += (pair[1].pos - pair[0].pos).abs() as f64 * overlap / (b - a) as f64;
+    }
+    distance / (end - start) as f64 * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(s: &mut FScript, at: i32, pos: i32) {
+        s.actions.push(FSPoint { at, pos });
+    }
+
+    /// slow strokes for 20s, a 10s pause, then 10s of fast strokes
+    fn slow_pause_fast() -> FScript {
+        let mut s = FScript::default();
+        for i in 0..=10 {
+            push(&mut s, i * 2000, if i % 2 == 0 { 0 } else { 100 });
+        }
+        for i in 0..=40 {
+            push(&mut s, 30000 + i * 250, if i % 2 == 0 { 0 } else { 100 });
+        }
+        s
+    }
+
+    #[test]
+    fn test_classify_sections() {
+        let sections = classify_sections(&slow_pause_fast(), Thresholds::default());
+        let labels: Vec<_> = sections.iter().map(|s| s.label).collect();
+        assert_eq!(
+            labels,
+            vec![SectionLabel::Slow, SectionLabel::Pause, SectionLabel::Fast]
+        );
+        // the labels change right where the motion does
+        assert_eq!(sections[0].start, 0);
+        assert_eq!(sections[1].start, 20000);
+        assert_eq!(sections[2].start, 30000);
+        assert_eq!(sections[2].end, 40000);
+        assert!((sections[0].average_speed - 50.0).abs() < 1.0);
+        assert!((sections[2].average_speed - 400.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let s = slow_pause_fast();
+        let map = heatmap(&s, 5000);
+        assert_eq!(map.len(), 8);
+        for (i, value) in map.iter().enumerate() {
+            let expected = match i {
+                0..=3 => 50.0 / HEATMAP_MAX_SPEED,
+                4 | 5 => 0.0,
+                _ => 1.0,
+            } as f32;
+            assert!((value - expected).abs() < 1e-6, "bucket {i} is {value}");
+        }
+        // buckets smaller than a segment get the same share of it
+        let fine = heatmap(&s, 500);
+        assert_eq!(fine.len(), 80);
+        assert!((fine[3] - fine[0]).abs() < 1e-6);
+        assert!(heatmap(&FScript::default(), 1000).is_empty());
+    }
+
+    #[test]
+    fn test_detect_strokes() {
+        let mut s = FScript::default();
+        for (at, pos) in [
+            (0, 0),
+            (100, 50),
+            (200, 90),
+            (300, 90),
+            (400, 20),
+            (600, 80),
+        ] {
+            push(&mut s, at, pos);
+        }
+        let strokes = detect_strokes(&s);
+        assert_eq!(strokes.len(), 3);
+        assert_eq!((strokes[0].start, strokes[0].end), (0, 200));
+        assert_eq!(strokes[0].depth(), 90);
+        assert!(strokes[0].is_up());
+        // the hold at 90 is not part of the down stroke
+        assert_eq!((strokes[1].start, strokes[1].end), (300, 400));
+        assert_eq!((strokes[1].from, strokes[1].to), (90, 20));
+        assert_eq!(strokes[2].duration_ms(), 200);
+
+        assert_eq!(detect_strokes(&slow_pause_fast()).len(), 10 + 40);
+    }
+
+    #[test]
+    fn test_tempo_curve() {
+        let curve = tempo_curve(&slow_pause_fast(), 4000);
+        assert_eq!(curve.len(), 21);
+        assert_eq!(curve[0].0, 0);
+        // slow strokes every 2s are 30 per minute, fast ones every 250ms are 240
+        assert_eq!(curve[4], (8000, 30.0));
+        assert_eq!(curve[12].1, 0.0);
+        assert_eq!(curve[18], (36000, 240.0));
+        assert!(tempo_curve(&FScript::default(), 1000).is_empty());
+    }
+
+    #[test]
+    fn test_auto_chapters() {
+        let mut s = slow_pause_fast();
+        s.metadata.chapters_mut().push(Chapter {
+            name: "manual".to_string(),
+            start_time: 25000,
+            end_time: 26000,
+        });
+        let options = AutoChapterOptions {
+            thresholds: Thresholds::default(),
+            ..Default::default()
+        };
+        assert_eq!(auto_chapters(&mut s, options), 3);
+        let names: Vec<_> = s
+            .metadata
+            .chapters()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["slow 1", "pause 1", "manual", "fast 1"]);
+
+        // with the default 30s minimum everything is absorbed into one chapter
+        let added = auto_chapters(
+            &mut s,
+            AutoChapterOptions {
+                replace_existing: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(added, 1);
+        assert_eq!(s.metadata.chapters().len(), 1);
+        assert_eq!(s.metadata.chapters()[0].end_time, 40000);
+    }
+
+    #[test]
+    fn test_auto_chapters_at_gaps() {
+        // strokes with two 20s holds in between
+        let mut s = FScript::default();
+        for part in 0..3 {
+            for i in 0..=40 {
+                push(
+                    &mut s,
+                    part * 40000 + i * 500,
+                    if i % 2 == 0 { 0 } else { 100 },
+                );
+            }
+        }
+        let options = AutoChapterOptions {
+            thresholds: Thresholds {
+                min_section_ms: 10_000,
+                ..Default::default()
+            },
+            split_on_intensity: false,
+            min_gap_ms: Some(10_000),
+            naming: ChapterNaming::Scene,
+            replace_existing: true,
+        };
+        assert_eq!(auto_chapters(&mut s, options), 3);
+        let chapters: Vec<_> = s
+            .metadata
+            .chapters()
+            .iter()
+            .map(|c| (c.name.as_str(), c.start_time, c.end_time))
+            .collect();
+        assert_eq!(
+            chapters,
+            vec![
+                ("Scene 1", 0, 30000),
+                ("Scene 2", 30000, 70000),
+                ("Scene 3", 70000, 100000)
+            ]
+        );
+
+        // with a 35s minimum either split leaves a 30s chapter, so neither is kept
+        let longer = AutoChapterOptions {
+            thresholds: Thresholds {
+                min_section_ms: 35_000,
+                ..Default::default()
+            },
+            ..options
+        };
+        assert_eq!(auto_chapters(&mut s, longer), 1);
+        let spans: Vec<_> = s
+            .metadata
+            .chapters()
+            .iter()
+            .map(|c| (c.start_time, c.end_time))
+            .collect();
+        assert_eq!(spans, vec![(0, 100000)]);
+    }
+
+    #[test]
+    fn test_find_gaps() {
+        let mut s = slow_pause_fast();
+        assert_eq!(
+            find_gaps(&s, 5000),
+            vec![Gap {
+                start: 20000,
+                end: 30000
+            }]
+        );
+        // strokes are movement however slow, only the held position is a gap
+        assert_eq!(find_gaps(&s, 2000).len(), 1);
+        push(&mut s, 45000, 0);
+        push(&mut s, 47000, 0);
+        assert_eq!(
+            find_gaps(&s, 2000)[1],
+            Gap {
+                start: 40000,
+                end: 47000
+            }
+        );
+
+        s.metadata.duration = 60;
+        for pt in s.actions.iter_mut() {
+            pt.at += 8000;
+        }
+        let gaps = find_gaps(&s, 5000);
+        let spans: Vec<_> = gaps.iter().map(|g| (g.start, g.end)).collect();
+        assert_eq!(spans, vec![(0, 8000), (28000, 38000), (48000, 60000)]);
+        assert_eq!(gaps[2].duration_ms(), 12000);
+    }
+
+    #[test]
+    fn test_classify_sections_no_flicker() {
+        // a single quick flick inside a pause is shorter than the minimum section
+        let mut s = FScript::default();
+        push(&mut s, 0, 50);
+        push(&mut s, 10000, 50);
+        push(&mut s, 10200, 100);
+        push(&mut s, 10400, 50);
+        push(&mut s, 20000, 50);
+        let sections = classify_sections(&s, Thresholds::default());
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].label, SectionLabel::Pause);
+        assert_eq!((sections[0].start, sections[0].end), (0, 20000));
+    }
+}
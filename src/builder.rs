@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use crate::{Chapter, FSPoint, FScript, FunscriptError};
+
+/// builds a [`FScript`] from scratch without touching the `-1` sentinels
+/// actions may be pushed in any order, [`FScriptBuilder::build`] sorts and checks them
+#[derive(Debug)]
+pub struct FScriptBuilder {
+    script: FScript,
+}
+
+impl Default for FScriptBuilder {
+    fn default() -> Self {
+        Self {
+            script: FScript {
+                version: "1.0".to_string(),
+                range: 100,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl FScript {
+    /// starts a [`FScriptBuilder`]
+    pub fn builder() -> FScriptBuilder {
+        FScriptBuilder::default()
+    }
+}
+
+impl FScriptBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.script.metadata.title = title.to_string();
+        self
+    }
+
+    pub fn creator(mut self, creator: &str) -> Self {
+        self.script.metadata.creator = creator.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.script.metadata.description = description.to_string();
+        self
+    }
+
+    pub fn video_url(mut self, url: &str) -> Self {
+        self.script.metadata.video_url = url.to_string();
+        self
+    }
+
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.script.metadata.tags.push(tag.to_string());
+        self
+    }
+
+    pub fn performer(mut self, performer: &str) -> Self {
+        self.script.metadata.performers.push(performer.to_string());
+        self
+    }
+
+    /// video length in seconds, defaults to the time of the last action
+    pub fn duration(mut self, seconds: i32) -> Self {
+        self.script.metadata.duration = seconds;
+        self
+    }
+
+    pub fn inverted(mut self, inverted: bool) -> Self {
+        self.script.inverted = inverted;
+        self
+    }
+
+    pub fn push_action(mut self, at: i32, pos: i32) -> Self {
+        self.script.actions.push(FSPoint { pos, at });
+        self
+    }
+
+    /// pushes an action at a time given as a [`Duration`]
+    pub fn push_action_at(self, at: Duration, pos: i32) -> Self {
+        let pt = FSPoint::from_duration(at, pos);
+        self.push_action(pt.at, pt.pos)
+    }
+
+    /// pushes `(at, pos)` pairs
+    pub fn actions(mut self, actions: impl IntoIterator<Item = (i32, i32)>) -> Self {
+        let points = actions.into_iter().map(|(at, pos)| FSPoint { pos, at });
+        self.script.actions.extend(points);
+        self
+    }
+
+    /// adds a chapter, times in ms
+    pub fn chapter(mut self, name: &str, start_ms: i64, end_ms: i64) -> Self {
+        self.script.metadata.chapters_mut().push(Chapter {
+            name: name.to_string(),
+            start_time: start_ms,
+            end_time: end_ms,
+        });
+        self
+    }
+
+    /// sorts the actions and checks every point is usable
+    /// points are reported by their index in push order, a later point at the
+    /// same time replaces an earlier one
+    pub fn build(mut self) -> Result<FScript, FunscriptError> {
+        let script = &mut self.script;
+        for (i, pt) in script.actions.iter().enumerate() {
+            if pt.at < 0 {
+                return Err(FunscriptError::OutOfRange {
+                    index: i,
+                    field: "at",
+                    value: pt.at as i64,
+                });
+            }
+            if !(0..=100).contains(&pt.pos) {
+                return Err(FunscriptError::OutOfRange {
+                    index: i,
+                    field: "pos",
+                    value: pt.pos as i64,
+                });
+            }
+        }
+        script.actions.sort_by_key(|pt| pt.at);
+        // dedup_by keeps the first of a run, swap so the last pushed one survives
+        script.actions.dedup_by(|later, earlier| {
+            let same = later.at == earlier.at;
+            if same {
+                std::mem::swap(later, earlier);
+            }
+            same
+        });
+
+        for (i, chapter) in script.metadata.chapters().iter().enumerate() {
+            if chapter.end_time < chapter.start_time {
+                return Err(FunscriptError::PointError("build chapter".to_string(), i));
+            }
+        }
+        script.metadata.chapters_mut().sort_by_key(|c| c.start_time);
+
+        if script.metadata.duration <= 0 {
+            if let Some(last) = script.actions.last() {
+                script.metadata.duration = (last.at + 999) / 1000;
+            }
+        }
+        Ok(self.script)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::validate;
+
+    #[test]
+    fn test_builder() {
+        let s = FScript::builder()
+            .title("demo")
+            .creator("me")
+            .tag("slow")
+            .push_action(1000, 100)
+            .push_action(0, 0)
+            .actions([(500, 20), (500, 50)])
+            .chapter("outro", 500, 1000)
+            .chapter("intro", 0, 500)
+            .build()
+            .unwrap();
+
+        let points: Vec<_> = s.actions.iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(points, vec![(0, 0), (500, 50), (1000, 100)]);
+        assert_eq!(s.metadata.title, "demo");
+        assert_eq!(s.metadata.duration, 1);
+        assert_eq!(s.metadata.chapters()[0].name, "intro");
+        assert_eq!((s.version.as_str(), s.range), ("1.0", 100));
+        assert!(validate(&s).is_empty());
+    }
+
+    #[test]
+    fn test_builder_rejects_bad_points() {
+        let err = FScriptBuilder::new()
+            .push_action(0, 0)
+            .push_action(100, 101)
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            FunscriptError::OutOfRange {
+                index: 1,
+                field: "pos",
+                value: 101
+            }
+        ));
+        assert!(FScript::builder().chapter("x", 10, 0).build().is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::{load_funscript, save_funscript, FScript, FunscriptError};
+
+/// a motion axis of a multi-axis device
+/// the names match the file suffixes used by OSR2/SR6 players
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Axis {
+    /// the main up/down axis, stored in the plain `video.funscript`
+    Stroke,
+    Surge,
+    Sway,
+    Twist,
+    Roll,
+    Pitch,
+    Vib,
+    Pump,
+    /// any other suffix, e.g. `video.lube.funscript`
+    Other(String),
+}
+
+impl Axis {
+    /// maps a file suffix such as `roll` to its axis
+    pub fn from_suffix(suffix: &str) -> Axis {
+        match suffix.to_ascii_lowercase().as_str() {
+            "" | "stroke" => Axis::Stroke,
+            "surge" => Axis::Surge,
+            "sway" => Axis::Sway,
+            "twist" => Axis::Twist,
+            "roll" => Axis::Roll,
+            "pitch" => Axis::Pitch,
+            "vib" => Axis::Vib,
+            "pump" => Axis::Pump,
+            _ => Axis::Other(suffix.to_string()),
+        }
+    }
+
+    /// the file suffix of the axis, empty for the stroke axis
+    pub fn suffix(&self) -> &str {
+        match self {
+            Axis::Stroke => "",
+            Axis::Surge => "surge",
+            Axis::Sway => "sway",
+            Axis::Twist => "twist",
+            Axis::Roll => "roll",
+            Axis::Pitch => "pitch",
+            Axis::Vib => "vib",
+            Axis::Pump => "pump",
+            Axis::Other(s) => s,
+        }
+    }
+
+    /// the file name of this axis for a base name like `video`
+    pub fn file_name(&self, base: &str) -> String {
+        match self {
+            Axis::Stroke => format!("{base}.funscript"),
+            axis => format!("{base}.{}.funscript", axis.suffix()),
+        }
+    }
+}
+
+/// all the axis scripts belonging to one video
+#[derive(Debug, Default)]
+pub struct FScriptBundle {
+    axes: BTreeMap<Axis, FScript>,
+}
+
+impl FScriptBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the script for an axis, if the bundle has one
+    pub fn axis(&self, axis: Axis) -> Option<&FScript> {
+        self.axes.get(&axis)
+    }
+
+    /// mutable access to the script for an axis
+    pub fn axis_mut(&mut self, axis: Axis) -> Option<&mut FScript> {
+        self.axes.get_mut(&axis)
+    }
+
+    /// adds or replaces the script for an axis, returning the old one
+    pub fn insert(&mut self, axis: Axis, script: FScript) -> Option<FScript> {
+        self.axes.insert(axis, script)
+    }
+
+    /// removes the script for an axis
+    pub fn remove(&mut self, axis: Axis) -> Option<FScript> {
+        self.axes.remove(&axis)
+    }
+
+    /// iterates the axes in a stable order, stroke first
+    pub fn iter(&self) -> impl Iterator<Item = (&Axis, &FScript)> {
+        self.axes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.axes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.axes.is_empty()
+    }
+
+    /// saves every axis next to `base_path` using the `video.<axis>.funscript` naming
+    pub fn save(&self, base_path: &str) -> Result<(), FunscriptError> {
+        let (dir, base) = split_base(base_path);
+        for (axis, script) in &self.axes {
+            let path = dir.join(axis.file_name(&base));
+            save_funscript(&path.to_string_lossy(), script)?;
+        }
+        Ok(())
+    }
+}
+
+/// loads every axis script for a video
+/// `base_path` can be the video, its main .funscript or the bare name without extension
+pub fn load_bundle(base_path: &str) -> Result<FScriptBundle, FunscriptError> {
+    let (dir, base) = split_base(base_path);
+    let mut bundle = FScriptBundle::new();
+    let read_dir = if dir.as_os_str().is_empty() {
+        std::fs::read_dir(".")?
+    } else {
+        std::fs::read_dir(&dir)?
+    };
+
+    for entry in read_dir {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else { continue };
+        let Some(rest) = name.strip_prefix(base.as_str()) else {
+            continue;
+        };
+        let Some(rest) = rest.strip_suffix(".funscript") else {
+            continue;
+        };
+        let axis = match rest.strip_prefix('.') {
+            Some(suffix) if !suffix.is_empty() => Axis::from_suffix(suffix),
+            None if rest.is_empty() => Axis::Stroke,
+            _ => continue,
+        };
+        let script = load_funscript(&entry.path().to_string_lossy())?;
+        bundle.insert(axis, script);
+    }
+    Ok(bundle)
+}
+
+/// splits a path into its directory and the base name without extensions
+fn split_base(base_path: &str) -> (PathBuf, String) {
+    let path = Path::new(base_path);
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(base_path);
+    let base = match name.strip_suffix(".funscript") {
+        Some(stem) => stem,
+        None => Path::new(name)
+            .file_stem()
+            .and_then(|n| n.to_str())
+            .unwrap_or(name),
+    };
+    (dir, base.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(dir: &str) {
+        let _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+        let ofs = "./test-scripts/openfunscripter.funscript";
+        let ai = "./test-scripts/scriptai-january-2022.funscript";
+        std::fs::copy(ofs, format!("{dir}/scene.funscript")).unwrap();
+        std::fs::copy(ai, format!("{dir}/scene.roll.funscript")).unwrap();
+        std::fs::copy(ai, format!("{dir}/scene.lube.funscript")).unwrap();
+        std::fs::copy(ai, format!("{dir}/scene2.funscript")).unwrap();
+    }
+
+    #[test]
+    fn test_load_bundle() {
+        let dir = "./test-scripts/out/bundle";
+        setup(dir);
+        let bundle = load_bundle(&format!("{dir}/scene.mp4")).unwrap();
+        assert_eq!(bundle.len(), 3);
+        assert_eq!(bundle.axis(Axis::Stroke).unwrap().actions.len(), 3585);
+        assert_eq!(bundle.axis(Axis::Roll).unwrap().actions.len(), 68);
+        assert!(bundle.axis(Axis::Other("lube".to_string())).is_some());
+        assert!(bundle.axis(Axis::Pitch).is_none());
+
+        let same = load_bundle(&format!("{dir}/scene.funscript")).unwrap();
+        assert_eq!(same.len(), 3);
+    }
+
+    #[test]
+    fn test_save_bundle() {
+        let dir = "./test-scripts/out/bundle-save";
+        setup(dir);
+        let mut bundle = load_bundle(&format!("{dir}/scene")).unwrap();
+        bundle.remove(Axis::Other("lube".to_string()));
+        bundle.save(&format!("{dir}/copy.mp4")).unwrap();
+        assert!(Path::new(&format!("{dir}/copy.roll.funscript")).exists());
+
+        let check = load_bundle(&format!("{dir}/copy")).unwrap();
+        let axes: Vec<_> = check.iter().map(|(axis, _)| axis.clone()).collect();
+        assert_eq!(axes, vec![Axis::Stroke, Axis::Roll]);
+    }
+}
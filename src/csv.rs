@@ -0,0 +1,104 @@
+use std::io::{BufRead, Write};
+
+use crate::{FSPoint, FScript, FunscriptError};
+
+/// writes the actions as `at,pos` lines with an `at,pos` header
+pub fn export_csv(script: &FScript, mut writer: impl Write) -> Result<(), FunscriptError> {
+    writeln!(writer, "at,pos")?;
+    for pt in &script.actions {
+        writeln!(writer, "{},{}", pt.at, pt.pos)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// reads `at,pos` lines into the actions of a new script
+/// a first line without any numbers is treated as a header, blank lines and `#`
+/// comments are skipped and the actions are sorted if the file wasn't
+pub fn import_csv(reader: impl BufRead) -> Result<FScript, FunscriptError> {
+    let mut script = FScript::default();
+    let mut first = true;
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let header = std::mem::take(&mut first) && is_header(line);
+        let mut cells = line.split(',').map(|c| c.trim().parse::<f64>());
+        match (cells.next(), cells.next()) {
+            (Some(Ok(at)), Some(Ok(pos))) if at.is_finite() && pos.is_finite() => {
+                script.actions.push(FSPoint {
+                    at: at.round() as i32,
+                    pos: pos.round() as i32,
+                });
+            }
+            _ if header => {}
+            _ => {
+                return Err(FunscriptError::Parse {
+                    path: None,
+                    line: idx + 1,
+                    column: 0,
+                    field: None,
+                    message: format!("expected at,pos but got \"{line}\""),
+                })
+            }
+        }
+    }
+    script.actions.sort_by_key(|pt| pt.at);
+    Ok(script)
+}
+
+/// true if no cell starts like a number, so `0;50` or `12.5,` are broken rows
+fn is_header(line: &str) -> bool {
+    line.split(',').all(|cell| {
+        !cell
+            .trim()
+            .starts_with(|c: char| c.is_ascii_digit() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_funscript;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let s = load_funscript("./test-scripts/scriptai-january-2022.funscript").unwrap();
+        let mut out = Vec::new();
+        export_csv(&s, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("at,pos\n"));
+        assert_eq!(text.lines().count(), 69);
+
+        let check = import_csv(text.as_bytes()).unwrap();
+        assert_eq!(check.actions.len(), 68);
+        assert_eq!(check.actions[10].at, s.actions[10].at);
+        assert_eq!(check.actions[10].pos, s.actions[10].pos);
+    }
+
+    #[test]
+    fn test_import_csv_without_header() {
+        let s =
+            import_csv("# from a spreadsheet\n200,10\n0, 50\n\n100.4,90.6\n".as_bytes()).unwrap();
+        let points: Vec<_> = s.actions.iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(points, vec![(0, 50), (100, 91), (200, 10)]);
+
+        let err = import_csv("at,pos\n0,0\n100,high\n".as_bytes()).unwrap_err();
+        assert!(
+            matches!(err, FunscriptError::Parse { line: 3, .. }),
+            "{err}"
+        );
+
+        let s = import_csv("# exported\n\nat,pos\n0,10\n".as_bytes()).unwrap();
+        assert_eq!(s.actions.len(), 1);
+        for bad in ["0,abc\n100,20\n", "0;50\n100,20\n", "12.5,\n100,20\n"] {
+            let err = import_csv(bad.as_bytes()).unwrap_err();
+            assert!(
+                matches!(err, FunscriptError::Parse { line: 1, .. }),
+                "{bad}"
+            );
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use crate::analysis::speed_between;
+use crate::filters::moving_average;
+use crate::{FSPoint, FScript};
+
+/// physical limits of a stroker device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviceProfile {
+    /// top speed in position units per second
+    pub max_speed: f64,
+    /// optional top acceleration in units per second squared
+    pub max_acceleration: Option<f64>,
+    /// delay between a command and the device starting to act on it
+    pub latency_ms: i64,
+    /// integration step of the simulation
+    pub step_ms: i64,
+}
+
+impl Default for DeviceProfile {
+    fn default() -> Self {
+        Self {
+            max_speed: 400.0,
+            max_acceleration: None,
+            latency_ms: 0,
+            step_ms: 10,
+        }
+    }
+}
+
+/// output of [`simulate_device`]
+#[derive(Debug)]
+pub struct SimulationResult {
+    /// where the device actually was, one point per simulation step
+    pub achieved: FScript,
+    /// mean absolute distance between target and achieved position
+    pub mean_error: f64,
+    /// largest distance between target and achieved position
+    pub max_error: f64,
+    /// percent of the simulated time spent at the speed limit
+    pub saturated_percent: f64,
+}
+
+/// predicts the motion a speed limited device would actually produce for a script
+/// the device chases the interpolated target position, delayed by the latency
+pub fn simulate_device(script: &FScript, profile: &DeviceProfile) -> SimulationResult {
+    let mut result = SimulationResult {
+        achieved: FScript::default(),
+        mean_error: 0.0,
+        max_error: 0.0,
+        saturated_percent: 0.0,
+    };
+    let (Some(first), Some(last)) = (script.actions.first(), script.actions.last()) else {
+        return result;
+    };
+
+    let step = profile.step_ms.max(1);
+    let dt = step as f64 / 1000.0;
+    let max_step = profile.max_speed * dt;
+    let mut pos = first.pos as f64;
+    let mut velocity = 0.0;
+    let mut error_sum = 0.0;
+    let mut saturated = 0;
+    let mut steps = 0;
+    let mut idx = 0;
+
+    let mut t = first.at as i64;
+    while t <= last.at as i64 + profile.latency_ms {
+        // target as it was commanded `latency_ms` ago
+        let cmd_t = t - profile.latency_ms;
+        while idx + 1 < script.actions.len() && script.actions[idx + 1].at as i64 <= cmd_t {
+            idx += 1;
+        }
+        let target = interpolate(&script.actions, idx, cmd_t);
+
+        let mut wanted = (target - pos) / dt;
+        if let Some(accel) = profile.max_acceleration {
+            let dv = accel * dt;
+            wanted = wanted.clamp(velocity - dv, velocity + dv);
+        }
+        velocity = wanted.clamp(-profile.max_speed, profile.max_speed);
+        if (velocity * dt).abs() >= max_step - f64::EPSILON && (target - pos).abs() > max_step {
+            saturated += 1;
+        }
+        pos = (pos + velocity * dt).clamp(0.0, 100.0);
+
+        let error = (interpolate(&script.actions, script_idx(&script.actions, t), t) - pos).abs();
+        error_sum += error;
+        result.max_error = result.max_error.max(error);
+        steps += 1;
+        result.achieved.actions.push(FSPoint {
+            at: t as i32,
+            pos: pos.round() as i32,
+        });
+        t += step;
+    }
+
+    result.mean_error = error_sum / steps as f64;
+    result.saturated_percent = saturated as f64 / steps as f64 * 100.0;
+    result
+}
+
+/// segments changed by [`limit_speed`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SpeedLimitReport {
+    /// index of the end point of every segment that was too fast
+    pub segments_modified: Vec<usize>,
+    /// fastest segment speed in the original script, units per second
+    pub max_speed_before: f64,
+    /// largest distance a position was moved
+    pub max_position_change: i32,
+}
+
+/// makes a script playable on a device with a top speed of `max_units_per_sec`
+/// timings are kept, the end of a too fast segment is pulled towards its start
+/// until the device can reach it, which shortens the stroke instead of desyncing it
+pub fn limit_speed(script: &mut FScript, max_units_per_sec: f64) -> SpeedLimitReport {
+    let mut report = SpeedLimitReport::default();
+    if max_units_per_sec <= 0.0 {
+        return report;
+    }
+    let original: Vec<i32> = script.actions.iter().map(|pt| pt.pos).collect();
+    for i in 1..script.actions.len() {
+        let (prev, cur) = (&script.actions[i - 1], &script.actions[i]);
+        let dt = (cur.at - prev.at) as f64 / 1000.0;
+        if dt > 0.0 {
+            let speed = (original[i] - original[i - 1]).abs() as f64 / dt;
+            report.max_speed_before = report.max_speed_before.max(speed);
+        }
+        // round down so the limited segment never ends up a bit too fast
+        let reach = (max_units_per_sec * dt.max(0.0)).floor() as i32;
+        let delta = cur.pos - prev.pos;
+        if delta.abs() > reach {
+            script.actions[i].pos = script.actions[i - 1].pos + reach * delta.signum();
+            report.segments_modified.push(i);
+            let change = (script.actions[i].pos - original[i]).abs();
+            report.max_position_change = report.max_position_change.max(change);
+        }
+    }
+    report
+}
+
+/// how [`to_vibration`] turns movement into vibration strength
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VibrationOptions {
+    /// stroke speed in units per second that maps to full intensity
+    pub full_speed: f64,
+    /// shape of the mapping, 1.0 is linear, below 1.0 boosts slow movement
+    pub gamma: f64,
+    /// time between output points
+    pub step_ms: i64,
+    /// moving average over the intensity curve, `None` keeps it raw
+    pub smoothing_window_ms: Option<i64>,
+}
+
+impl Default for VibrationOptions {
+    fn default() -> Self {
+        Self {
+            full_speed: 400.0,
+            gamma: 0.7,
+            step_ms: 100,
+            smoothing_window_ms: Some(500),
+        }
+    }
+}
+
+/// converts a stroke script into an intensity curve for vibration-only devices
+/// each output point is the local speed around it mapped to 0-100
+pub fn to_vibration(script: &FScript, options: VibrationOptions) -> FScript {
+    let mut out = FScript {
+        version: script.version.clone(),
+        range: script.range,
+        ..Default::default()
+    };
+    let (Some(first), Some(last)) = (script.actions.first(), script.actions.last()) else {
+        return out;
+    };
+    let step = options.step_ms.max(1);
+    let gamma = if options.gamma > 0.0 {
+        options.gamma
+    } else {
+        1.0
+    };
+    let mut t = first.at as i64;
+    while t <= last.at as i64 {
+        let speed = speed_between(&script.actions, t - step / 2, t + step - step / 2);
+        let level = (speed / options.full_speed).clamp(0.0, 1.0).powf(gamma);
+        out.actions.push(FSPoint {
+            at: t as i32,
+            pos: (level * 100.0).round() as i32,
+        });
+        t += step;
+    }
+    if let Some(window) = options.smoothing_window_ms {
+        moving_average(&mut out.actions, window);
+    }
+    out
+}
+
+/// index of the last action at or before `t`
+fn script_idx(actions: &[FSPoint], t: i64) -> usize {
+    actions
+        .partition_point(|pt| pt.at as i64 <= t)
+        .saturating_sub(1)
+}
+
+/// position between `actions[idx]` and the next action at time `t`
+fn interpolate(actions: &[FSPoint], idx: usize, t: i64) -> f64 {
+    let cur = &actions[idx];
+    match actions.get(idx + 1) {
+        Some(next) if t > cur.at as i64 => {
+            let frac = (t - cur.at as i64) as f64 / (next.at - cur.at) as f64;
+            cur.pos as f64 + (next.pos - cur.pos) as f64 * frac.min(1.0)
+        }
+        _ => cur.pos as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strokes(interval: i32, count: i32) -> FScript {
+        let mut s = FScript::default();
+        for i in 0..count {
+            s.actions.push(FSPoint {
+                at: i * interval,
+                pos: if i % 2 == 0 { 0 } else { 100 },
+            });
+        }
+        s
+    }
+
+    #[test]
+    fn test_simulate_too_fast() {
+        // full strokes every 100ms need 1000 units/s
+        let s = strokes(100, 20);
+        let profile = DeviceProfile {
+            max_speed: 250.0,
+            ..Default::default()
+        };
+        let result = simulate_device(&s, &profile);
+        assert!(result.max_error > 50.0, "max error {}", result.max_error);
+        assert!(result.mean_error > 20.0, "mean error {}", result.mean_error);
+        assert!(result.saturated_percent > 80.0);
+        assert!(!result.achieved.actions.is_empty());
+    }
+
+    #[test]
+    fn test_limit_speed() {
+        let mut s = strokes(100, 6);
+        let report = limit_speed(&mut s, 400.0);
+        assert_eq!(report.segments_modified, vec![1, 3, 5]);
+        assert_eq!(report.max_speed_before, 1000.0);
+        assert_eq!(report.max_position_change, 60);
+        let positions: Vec<_> = s.actions.iter().map(|pt| pt.pos).collect();
+        assert_eq!(positions, vec![0, 40, 0, 40, 0, 40]);
+        assert_eq!(s.actions[5].at, 500);
+
+        let result = simulate_device(&s, &DeviceProfile::default());
+        assert!(result.max_error < 5.0, "max error {}", result.max_error);
+
+        let mut slow = strokes(1000, 4);
+        assert!(limit_speed(&mut slow, 400.0).segments_modified.is_empty());
+    }
+
+    #[test]
+    fn test_to_vibration() {
+        // 1000 units/s for 2s, then held still for 2s
+        let mut s = strokes(100, 21);
+        s.actions.push(FSPoint { at: 4000, pos: 0 });
+        let options = VibrationOptions {
+            smoothing_window_ms: None,
+            ..Default::default()
+        };
+        let vib = to_vibration(&s, options);
+        assert_eq!(vib.actions.len(), 41);
+        assert_eq!(vib.actions[10].pos, 100);
+        assert_eq!(vib.actions[30].pos, 0);
+
+        let linear = VibrationOptions {
+            full_speed: 2000.0,
+            gamma: 1.0,
+            ..options
+        };
+        assert_eq!(to_vibration(&s, linear).actions[10].pos, 50);
+        let smooth = to_vibration(&s, VibrationOptions::default());
+        assert!(smooth.actions[20].pos > 0 && smooth.actions[20].pos < 100);
+    }
+
+    #[test]
+    fn test_simulate_slow_script_tracks() {
+        // full strokes every second only need 100 units/s
+        let s = strokes(1000, 10);
+        let result = simulate_device(&s, &DeviceProfile::default());
+        assert!(result.max_error < 5.0, "max error {}", result.max_error);
+        assert_eq!(result.saturated_percent, 0.0);
+    }
+}
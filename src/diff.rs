@@ -0,0 +1,198 @@
+use serde_json::Value;
+
+use crate::{FSPoint, FScript, FunscriptError};
+
+/// an added and a removed point at most this far apart count as one moved point
+pub const MOVE_WINDOW_MS: i32 = 100;
+
+/// a point that changed time or position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PointMove {
+    pub from: FSPoint,
+    pub to: FSPoint,
+}
+
+/// a metadata field with a different value
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataChange {
+    /// the json name of the field, e.g. `title` or `video_url`
+    pub field: String,
+    /// `None` if the field wasn't set
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// what changed between two versions of a script, see [`diff`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptDiff {
+    pub added: Vec<FSPoint>,
+    pub removed: Vec<FSPoint>,
+    pub moved: Vec<PointMove>,
+    pub metadata: Vec<MetadataChange>,
+}
+
+impl ScriptDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.moved.is_empty()
+            && self.metadata.is_empty()
+    }
+
+    /// applies the diff to a script as a patch
+    /// every removed or moved point must still be in the script, otherwise nothing
+    /// is changed and the error names the first missing one
+    pub fn apply(&self, script: &mut FScript) -> Result<(), FunscriptError> {
+        let mut actions = script.actions.clone();
+        let gone = self
+            .removed
+            .iter()
+            .chain(self.moved.iter().map(|m| &m.from));
+        for (i, pt) in gone.enumerate() {
+            let Ok(idx) = actions.binary_search(pt) else {
+                return Err(FunscriptError::PointError("patch".to_string(), i));
+            };
+            actions.remove(idx);
+        }
+        let new = self.added.iter().chain(self.moved.iter().map(|m| &m.to));
+        for pt in new {
+            match actions.binary_search_by_key(&pt.at, |p| p.at) {
+                Ok(idx) => actions[idx] = pt.clone(),
+                Err(idx) => actions.insert(idx, pt.clone()),
+            }
+        }
+
+        let mut metadata = serde_json::to_value(&script.metadata)?;
+        if let Value::Object(fields) = &mut metadata {
+            for change in &self.metadata {
+                match &change.after {
+                    Some(value) => fields.insert(change.field.clone(), value.clone()),
+                    None => fields.remove(&change.field),
+                };
+            }
+        }
+        script.metadata = serde_json::from_value(metadata)?;
+        script.actions = actions;
+        Ok(())
+    }
+}
+
+/// compares the actions and metadata of `a` and `b`
+/// points in both are unchanged, an unmatched pair within [`MOVE_WINDOW_MS`] of
+/// each other is reported as moved and everything else as added or removed
+pub fn diff(a: &FScript, b: &FScript) -> ScriptDiff {
+    let mut result = ScriptDiff::default();
+    let (old, new) = unmatched(sorted(&a.actions), sorted(&b.actions));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        let (from, to) = (&old[i], &new[j]);
+        if (from.at - to.at).abs() <= MOVE_WINDOW_MS {
+            result.moved.push(PointMove {
+                from: from.clone(),
+                to: to.clone(),
+            });
+            i += 1;
+            j += 1;
+        } else if from.at < to.at {
+            result.removed.push(from.clone());
+            i += 1;
+        } else {
+            result.added.push(to.clone());
+            j += 1;
+        }
+    }
+    result.removed.extend_from_slice(&old[i..]);
+    result.added.extend_from_slice(&new[j..]);
+
+    let fields = |script: &FScript| match serde_json::to_value(&script.metadata) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Default::default(),
+    };
+    let (before, after) = (fields(a), fields(b));
+    let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let (was, now) = (before.get(key), after.get(key));
+        if was != now {
+            result.metadata.push(MetadataChange {
+                field: key.clone(),
+                before: was.cloned(),
+                after: now.cloned(),
+            });
+        }
+    }
+    result
+}
+
+fn sorted(points: &[FSPoint]) -> Vec<FSPoint> {
+    let mut points = points.to_vec();
+    points.sort();
+    points
+}
+
+/// drops the points found in both sorted lists, what is left was removed from the
+/// first or added in the second
+fn unmatched(old: Vec<FSPoint>, new: Vec<FSPoint>) -> (Vec<FSPoint>, Vec<FSPoint>) {
+    let (mut only_old, mut only_new) = (Vec::new(), Vec::new());
+    let mut new = new.into_iter().peekable();
+    for pt in old {
+        while let Some(n) = new.next_if(|n| *n < pt) {
+            only_new.push(n);
+        }
+        if new.next_if_eq(&pt).is_none() {
+            only_old.push(pt);
+        }
+    }
+    only_new.extend(new);
+    (only_old, only_new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_funscript;
+
+    #[test]
+    fn test_diff() {
+        let a = load_funscript("./test-scripts/scriptai-january-2022.funscript").unwrap();
+        let mut b = a.clone();
+        assert!(diff(&a, &b).is_empty());
+
+        b.actions.remove(10);
+        b.actions[20].pos = (b.actions[20].pos + 30) % 100;
+        b.actions[30].at += 40;
+        b.actions.push(FSPoint {
+            at: b.actions.last().unwrap().at + 5000,
+            pos: 50,
+        });
+        b.metadata.title = "edited".to_string();
+
+        let d = diff(&a, &b);
+        assert_eq!(d.removed, vec![a.actions[10].clone()]);
+        assert_eq!(d.added.len(), 1);
+        assert_eq!(d.moved.len(), 2);
+        assert_eq!(d.moved[1].to.at, a.actions[31].at + 40);
+        assert_eq!(d.metadata.len(), 1);
+        assert_eq!(d.metadata[0].field, "title");
+        assert_eq!(d.metadata[0].after, Some(Value::from("edited")));
+    }
+
+    #[test]
+    fn test_apply_diff() {
+        let a = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        let mut b = a.clone();
+        b.actions.drain(100..110);
+        b.actions[500].pos = 0;
+        b.metadata.tags.push("patched".to_string());
+
+        let patch = diff(&a, &b);
+        let mut c = a.clone();
+        patch.apply(&mut c).unwrap();
+        assert_eq!(c, b);
+
+        // the removed points are gone now, so the patch no longer applies
+        assert!(patch.apply(&mut c).is_err());
+        assert_eq!(c, b);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{FSPoint, FScript};
+
+/// smooths the script's actions with a centered moving average over `window_ms`
+/// use [`moving_average`] directly to smooth `raw_actions`
+pub fn smooth_moving_average(script: &mut FScript, window_ms: i64) {
+    moving_average(&mut script.actions, window_ms);
+}
+
+/// replaces each position with the mean of all points within `window_ms / 2` of it
+/// points must be sorted by time, timestamps are left untouched
+pub fn moving_average(points: &mut [FSPoint], window_ms: i64) {
+    if points.len() < 3 || window_ms <= 0 {
+        return;
+    }
+    let half = window_ms / 2;
+    let mut prefix = Vec::with_capacity(points.len() + 1);
+    prefix.push(0i64);
+    for pt in points.iter() {
+        prefix.push(prefix[prefix.len() - 1] + pt.pos as i64);
+    }
+
+    let times: Vec<i64> = points.iter().map(|pt| pt.at as i64).collect();
+    let (mut lo, mut hi) = (0, 0);
+    for (i, pt) in points.iter_mut().enumerate() {
+        while times[lo] < times[i] - half {
+            lo += 1;
+        }
+        while hi < times.len() && times[hi] <= times[i] + half {
+            hi += 1;
+        }
+        let sum = prefix[hi] - prefix[lo];
+        pt.pos = (sum as f64 / (hi - lo) as f64).round() as i32;
+    }
+}
+
+/// smooths the script's actions with a Savitzky-Golay filter
+/// keeps peaks and valleys much better than a moving average of the same window
+pub fn smooth_savitzky_golay(script: &mut FScript, window_ms: i64, order: usize) {
+    savitzky_golay(&mut script.actions, window_ms, order);
+}
+
+/// fits a polynomial of `order` (at most 4) by least squares to all points within
+/// `window_ms / 2` of each point and replaces the position with the fitted value
+/// works on irregularly spaced points, windows with too few points are left alone
+pub fn savitzky_golay(points: &mut [FSPoint], window_ms: i64, order: usize) {
+    let order = order.min(4);
+    if points.len() < 3 || window_ms <= 0 {
+        return;
+    }
+    let half = window_ms / 2;
+    let original: Vec<(i64, f64)> = points
+        .iter()
+        .map(|pt| (pt.at as i64, pt.pos as f64))
+        .collect();
+
+    let (mut lo, mut hi) = (0, 0);
+    for (i, pt) in points.iter_mut().enumerate() {
+        let (t, _) = original[i];
+        while original[lo].0 < t - half {
+            lo += 1;
+        }
+        while hi < original.len() && original[hi].0 <= t + half {
+            hi += 1;
+        }
+        if hi - lo <= order {
+            continue;
+        }
+        if let Some(value) = fit_at_center(&original[lo..hi], t, half.max(1), order) {
+            pt.pos = value.round().clamp(0.0, 100.0) as i32;
+        }
+    }
+}
+
+/// least squares polynomial fit around `center`, returning its value at `center`
+fn fit_at_center(window: &[(i64, f64)], center: i64, scale: i64, order: usize) -> Option<f64> {
+    let n = order + 1;
+    // normal equations a * c = b with times scaled to -1..1 for conditioning
+    let mut a = [[0.0f64; 6]; 5];
+    for &(t, pos) in window {
+        let x = (t - center) as f64 / scale as f64;
+        let mut powers = [1.0f64; 9];
+        for k in 1..powers.len() {
+            powers[k] = powers[k - 1] * x;
+        }
+        for row in 0..n {
+            for col in 0..n {
+                a[row][col] += powers[row + col];
+            }
+            a[row][n] += powers[row] * pos;
+        }
+    }
+
+    // gaussian elimination with partial pivoting
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&x, &y| a[x][col].abs().total_cmp(&a[y][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        for row in 0..n {
+            if row != col {
+                let factor = a[row][col] / a[col][col];
+                let pivot_row = a[col];
+                for (value, p) in a[row].iter_mut().zip(pivot_row).skip(col).take(n + 1 - col) {
+                    *value -= factor * p;
+                }
+            }
+        }
+    }
+    Some(a[0][n] / a[0][0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jittery() -> FScript {
+        let mut s = FScript::default();
+        for i in 0..100 {
+            let jitter = if i % 2 == 0 { 4 } else { -4 };
+            s.actions.push(FSPoint {
+                at: i * 10,
+                pos: 50 + jitter,
+            });
+        }
+        s
+    }
+
+    #[test]
+    fn test_moving_average_removes_jitter() {
+        let mut s = jittery();
+        smooth_moving_average(&mut s, 40);
+        assert!(s.actions[5..95].iter().all(|pt| (pt.pos - 50).abs() <= 1));
+        assert_eq!(s.actions.len(), 100);
+        assert_eq!(s.actions[42].at, 420);
+    }
+
+    #[test]
+    fn test_savitzky_golay_keeps_peaks() {
+        // a smooth 0-100 wave sampled every 20ms with a 1s period
+        let wave = || {
+            let mut s = FScript::default();
+            for i in 0..200 {
+                let phase = i as f64 * 20.0 / 1000.0 * std::f64::consts::TAU;
+                s.actions.push(FSPoint {
+                    at: i * 20,
+                    pos: (50.0 + 50.0 * phase.cos()).round() as i32,
+                });
+            }
+            s
+        };
+        let peak = |s: &FScript| s.actions[50..150].iter().map(|pt| pt.pos).max().unwrap();
+
+        let mut ma = wave();
+        smooth_moving_average(&mut ma, 300);
+        let mut sg = wave();
+        smooth_savitzky_golay(&mut sg, 300, 2);
+        assert!(peak(&sg) >= 98, "sg peak {}", peak(&sg));
+        assert!(peak(&ma) < peak(&sg) - 4, "ma peak {}", peak(&ma));
+
+        // jitter is still removed
+        let mut s = jittery();
+        smooth_savitzky_golay(&mut s, 100, 2);
+        assert!(s.actions[10..90].iter().all(|pt| (pt.pos - 50).abs() <= 1));
+    }
+
+    #[test]
+    fn test_moving_average_raw_and_small_window() {
+        let mut s = jittery();
+        s.raw_actions = std::mem::take(&mut s.actions);
+        moving_average(&mut s.raw_actions, 5);
+        // nothing else falls in a 5ms window
+        assert_eq!(s.raw_actions[0].pos, 54);
+        moving_average(&mut s.raw_actions, 20);
+        assert_eq!(s.raw_actions[1].pos, 51);
+    }
+}
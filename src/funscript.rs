@@ -1,23 +1,66 @@
+pub mod analysis;
+pub mod builder;
+pub mod bundle;
+pub mod csv;
+pub mod device;
+pub mod diff;
+pub mod filters;
+pub mod handy;
+pub mod legacy;
+pub mod patterns;
+pub mod player;
+pub mod publish;
+pub mod recording;
+pub mod recover;
+pub mod render;
+pub mod session;
+pub mod simplify;
+pub mod stats;
+pub mod subtitles;
+pub mod sync;
+pub mod tcode;
+pub mod template;
+pub mod transform;
+pub mod units;
+pub mod validate;
+pub mod vorze;
+
+use std::io::{Read, Write};
+use std::str::FromStr;
+
 use mint::Point2;
 use ramer_douglas_peucker::rdp;
 use serde::{Deserialize, Serialize};
-use serde_json::{Error as SerdeError, Value};
+use serde_json::{Error as SerdeError, Map, Value};
 use thiserror::Error;
 
 /// A .funscript action point
 /// x = pos
 /// y = at
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
 pub struct FSPoint {
     pub pos: i32,
     pub at: i32,
 }
 
+/// points are ordered by time, then by position
+impl Ord for FSPoint {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.at, self.pos).cmp(&(other.at, other.pos))
+    }
+}
+
+impl PartialOrd for FSPoint {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// properties about a pressure simulator
 /// that can be used to input points in a .funscript
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SimulatorPresets {
     pub name: String,
     pub full_range: bool,
@@ -27,14 +70,17 @@ pub struct SimulatorPresets {
     pub width: f32,
     pub offset: String,
     pub color: String,
+    /// unknown fields, kept so they survive a load/save cycle
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// extra metadata, specifically for OpenFunscripter (OFS)
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct OFSMetadata {
     bookmarks: Vec<i32>,
-    chapters: Vec<String>,
+    chapters: Vec<Chapter>,
     creator: String,
     description: String,
     duration: i32,
@@ -49,11 +95,127 @@ pub struct OFSMetadata {
     ofs_type: String,
     #[serde(rename = "video_url")]
     video_url: String,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl OFSMetadata {
+    /// the chapters of the script in file order
+    pub fn chapters(&self) -> &[Chapter] {
+        &self.chapters
+    }
+
+    /// mutable access to the chapters of the script
+    pub fn chapters_mut(&mut self) -> &mut Vec<Chapter> {
+        &mut self.chapters
+    }
+
+    /// the first chapter covering the given time in ms
+    pub fn chapter_at(&self, ms: i64) -> Option<&Chapter> {
+        self.chapters.iter().find(|c| c.contains(ms))
+    }
+}
+
+/// a named section of a script, as written by OFS 3.x
+/// times are stored in ms and written as `HH:MM:SS.mmm`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub name: String,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+impl Chapter {
+    /// true if `ms` falls in `start_time..end_time`
+    pub fn contains(&self, ms: i64) -> bool {
+        ms >= self.start_time && ms < self.end_time
+    }
+}
+
+impl Serialize for Chapter {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Chapter", 3)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("startTime", &format_timestamp(self.start_time))?;
+        state.serialize_field("endTime", &format_timestamp(self.end_time))?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Chapter {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Time {
+            Ms(i64),
+            Stamp(String),
+        }
+
+        // older scripts store chapters as bare names
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            #[serde(rename_all = "camelCase")]
+            Full {
+                name: String,
+                start_time: Time,
+                end_time: Time,
+            },
+            Name(String),
+        }
+
+        let time = |t: Time| match t {
+            Time::Ms(ms) => Ok(ms),
+            Time::Stamp(s) => parse_timestamp(&s)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid chapter time {s:?}"))),
+        };
+        match Repr::deserialize(deserializer)? {
+            Repr::Full {
+                name,
+                start_time,
+                end_time,
+            } => Ok(Chapter {
+                name,
+                start_time: time(start_time)?,
+                end_time: time(end_time)?,
+            }),
+            Repr::Name(name) => Ok(Chapter {
+                name,
+                start_time: 0,
+                end_time: 0,
+            }),
+        }
+    }
+}
+
+/// formats ms as `HH:MM:SS.mmm`
+fn format_timestamp(ms: i64) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// parses `HH:MM:SS.mmm`, `MM:SS.mmm` or `SS.mmm` into ms
+fn parse_timestamp(s: &str) -> Option<i64> {
+    let mut parts = s.trim().rsplit(':');
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    let hours: i64 = parts.next().map_or(Some(0), |h| h.parse().ok())?;
+    if parts.next().is_some() || !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+    Some((hours * 3600 + minutes * 60) * 1000 + (seconds * 1000.0).round() as i64)
 }
 
 /// a serializable and deserializable .funscript file
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase", default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct FScript {
     pub version: String,
     pub inverted: bool,
@@ -73,6 +235,9 @@ pub struct FScript {
     pub actions: Vec<FSPoint>,
     pub raw_actions: Vec<FSPoint>,
     pub metadata: OFSMetadata,
+    /// unknown fields, kept so they survive a load/save cycle
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for FScript {
@@ -95,27 +260,260 @@ impl Default for FScript {
             clips: Vec::new(),
             actions: Vec::new(),
             raw_actions: Vec::new(),
-            metadata: OFSMetadata {
-                bookmarks: Vec::new(),
-                chapters: Vec::new(),
-                creator: "".to_string(),
-                description: "".to_string(),
-                duration: -1,
-                license: "".to_string(),
-                notes: "".to_string(),
-                performers: Vec::new(),
-                script_url: "".to_string(),
-                tags: Vec::new(),
-                title: "".to_string(),
-                ofs_type: "".to_string(),
-                video_url: "".to_string(),
-            },
+            metadata: OFSMetadata::default(),
+            extra: Map::new(),
+        }
+    }
+}
+
+impl Default for OFSMetadata {
+    fn default() -> Self {
+        Self {
+            bookmarks: Vec::new(),
+            chapters: Vec::new(),
+            creator: "".to_string(),
+            description: "".to_string(),
+            duration: -1,
+            license: "".to_string(),
+            notes: "".to_string(),
+            performers: Vec::new(),
+            script_url: "".to_string(),
+            tags: Vec::new(),
+            title: "".to_string(),
+            ofs_type: "".to_string(),
+            video_url: "".to_string(),
+            extra: Map::new(),
+        }
+    }
+}
+
+/// parses .funscript json text the same way [`load_funscript`] does
+impl FromStr for FScript {
+    type Err = FunscriptError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (script, _) = parse_funscript(text, ParseMode::Strict)?;
+        Ok(script)
+    }
+}
+
+/// how a script is written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// indent the json, otherwise it is written on a single line
+    pub pretty: bool,
+    /// leave out top level fields that still hold their default value
+    /// `actions` is always written
+    pub skip_defaults: bool,
+    /// write `rawActions` empty, recordings are rarely wanted in a published script
+    pub strip_raw_actions: bool,
+}
+
+impl Default for SaveOptions {
+    /// everything is kept, pretty printed
+    fn default() -> Self {
+        Self {
+            pretty: true,
+            skip_defaults: false,
+            strip_raw_actions: false,
+        }
+    }
+}
+
+impl SaveOptions {
+    /// the smallest output, for publishing
+    pub fn minimal() -> Self {
+        Self {
+            pretty: false,
+            skip_defaults: true,
+            strip_raw_actions: true,
+        }
+    }
+}
+
+/// how strictly a .funscript file is parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// missing metadata fields, mistyped fields and duplicate keys are errors
+    #[default]
+    Strict,
+    /// bad fields are dropped with a warning and left at their defaults
+    /// bad entries in `actions` and `rawActions` are coerced or skipped one by one
+    Lenient,
+}
+
+/// a problem that lenient parsing worked around
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadWarning {
+    /// the offending field, nested fields are written as `metadata.chapters`
+    pub field: String,
+    pub message: String,
+}
+
+impl FScript {
+    /// builds a script from an already parsed json value
+    /// useful when the script is embedded in a larger json document
+    pub fn from_value(
+        value: Value,
+        mode: ParseMode,
+    ) -> Result<(FScript, Vec<LoadWarning>), FunscriptError> {
+        match mode {
+            ParseMode::Strict => Ok((serde_json::from_value(value)?, Vec::new())),
+            ParseMode::Lenient => from_value_lenient(value),
+        }
+    }
+
+    /// parses a strict .funscript from any reader, e.g. a network stream or archive entry
+    pub fn from_reader(mut reader: impl Read) -> Result<FScript, FunscriptError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        text.parse()
+    }
+
+    /// parses a strict .funscript from in-memory bytes
+    pub fn from_slice(bytes: &[u8]) -> Result<FScript, FunscriptError> {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        text.parse()
+    }
+
+    /// writes the script as pretty printed json, e.g. into a response body
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), FunscriptError> {
+        Ok(serde_json::to_writer_pretty(writer, self)?)
+    }
+
+    /// the script as pretty printed json bytes
+    pub fn to_vec(&self) -> Result<Vec<u8>, FunscriptError> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// the script as pretty printed json, the format [`save_funscript`] writes
+    pub fn to_string_pretty(&self) -> Result<String, FunscriptError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// the script as single line json without any whitespace
+    pub fn to_string_compact(&self) -> Result<String, FunscriptError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// the script as json formatted according to `options`
+    pub fn to_string_with(&self, options: &SaveOptions) -> Result<String, FunscriptError> {
+        if !options.skip_defaults && !options.strip_raw_actions {
+            return match options.pretty {
+                true => self.to_string_pretty(),
+                false => self.to_string_compact(),
+            };
+        }
+        let value = self.to_value_with(options)?;
+        Ok(match options.pretty {
+            true => serde_json::to_string_pretty(&value)?,
+            false => serde_json::to_string(&value)?,
+        })
+    }
+
+    /// the script as a json value with `skip_defaults` and `strip_raw_actions` applied
+    pub fn to_value_with(&self, options: &SaveOptions) -> Result<Value, FunscriptError> {
+        let mut value = self.to_value()?;
+        if let Value::Object(fields) = &mut value {
+            if options.strip_raw_actions {
+                fields.insert("rawActions".to_string(), Value::Array(Vec::new()));
+            }
+            if options.skip_defaults {
+                let defaults = FScript::default().to_value()?;
+                fields.retain(|key, v| key == "actions" || defaults.get(key) != Some(v));
+            }
+        }
+        Ok(value)
+    }
+
+    /// converts the script into a json value for embedding in other documents
+    pub fn to_value(&self) -> Result<Value, FunscriptError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    /// the actions as a player should use them, mirrored if `inverted` is set
+    pub fn resolved_actions(&self) -> Vec<FSPoint> {
+        self.actions
+            .iter()
+            .map(|pt| FSPoint {
+                at: pt.at,
+                pos: if self.inverted { 100 - pt.pos } else { pt.pos },
+            })
+            .collect()
+    }
+
+    /// iterates over each pair of consecutive actions
+    pub fn segments(&self) -> impl Iterator<Item = Segment<'_>> {
+        self.actions.windows(2).map(|pair| Segment {
+            start: &pair[0],
+            end: &pair[1],
+        })
+    }
+
+    /// position at an arbitrary time, linearly interpolated between the surrounding actions
+    /// times before the first or after the last action hold that action's position
+    /// `None` if the script has no actions
+    pub fn pos_at(&self, ms: i64) -> Option<u8> {
+        interpolate(&self.actions, ms).map(|pos| pos.round().clamp(0.0, 100.0) as u8)
+    }
+
+    /// the actions with `start_ms <= at <= end_ms`, found by binary search
+    /// actions must be sorted by time
+    pub fn actions_in_range(&self, start_ms: i64, end_ms: i64) -> &[FSPoint] {
+        let start = self.actions.partition_point(|pt| (pt.at as i64) < start_ms);
+        let end = self.actions.partition_point(|pt| (pt.at as i64) <= end_ms);
+        &self.actions[start..end.max(start)]
+    }
+}
+
+/// the move between two consecutive actions, see [`FScript::segments`]
+#[derive(Debug, Clone, Copy)]
+pub struct Segment<'a> {
+    pub start: &'a FSPoint,
+    pub end: &'a FSPoint,
+}
+
+impl Segment<'_> {
+    pub fn duration_ms(&self) -> i64 {
+        self.end.at as i64 - self.start.at as i64
+    }
+
+    /// position change, positive when moving up
+    pub fn delta(&self) -> i32 {
+        self.end.pos - self.start.pos
+    }
+
+    /// absolute speed in position units per second
+    /// infinite for a jump between two points at the same time
+    pub fn speed(&self) -> f64 {
+        let distance = self.delta().abs() as f64;
+        match self.duration_ms() {
+            0 if distance == 0.0 => 0.0,
+            0 => f64::INFINITY,
+            ms => distance / ms as f64 * 1000.0,
         }
     }
 }
 
+/// unrounded position at `ms`, see [`FScript::pos_at`]
+/// `actions` must be sorted by time
+pub(crate) fn interpolate(actions: &[FSPoint], ms: i64) -> Option<f64> {
+    let idx = actions.partition_point(|pt| (pt.at as i64) <= ms);
+    match (idx.checked_sub(1).map(|i| &actions[i]), actions.get(idx)) {
+        (Some(prev), Some(next)) => {
+            let frac = (ms - prev.at as i64) as f64 / (next.at - prev.at) as f64;
+            Some(prev.pos as f64 + (next.pos - prev.pos) as f64 * frac)
+        }
+        (Some(prev), None) => Some(prev.pos as f64),
+        (None, Some(next)) => Some(next.pos as f64),
+        (None, None) => None,
+    }
+}
+
 /// Error types for .funscript file operations
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum FunscriptError {
     #[error("file read error {0}")]
     FileReadError(#[from] std::io::Error),
@@ -123,30 +521,406 @@ pub enum FunscriptError {
     JsonError(#[from] SerdeError),
     #[error("failed to {0} point at index {1}")]
     PointError(String, usize),
+    #[error("duplicate key \"{key}\" at byte {first_offset} and {second_offset}")]
+    DuplicateKey {
+        key: String,
+        first_offset: usize,
+        second_offset: usize,
+    },
+    #[error("player error {0}")]
+    PlayerError(String),
+    #[error("invalid file extension for {path}, expected {expected}")]
+    InvalidExtension {
+        path: String,
+        expected: &'static str,
+    },
+    #[error("script has no actions")]
+    EmptyScript,
+    /// the point at `index` is not later than the one before it
+    #[error("point at index {index} is not after the previous point")]
+    NonMonotonicTimestamps { index: usize },
+    /// a point has a negative time or a position outside 0-100
+    #[error("{field} {value} of point at index {index} is out of range")]
+    OutOfRange {
+        index: usize,
+        field: &'static str,
+        value: i64,
+    },
+    /// malformed json or a field with the wrong type, with the position in the text
+    #[error("{}", parse_message(path, *line, *column, field, message))]
+    Parse {
+        /// the file being parsed, `None` when parsing from memory
+        path: Option<String>,
+        line: usize,
+        column: usize,
+        /// the key the error happened in, when it could be found
+        field: Option<String>,
+        message: String,
+    },
+}
+
+fn parse_message(
+    path: &Option<String>,
+    line: usize,
+    column: usize,
+    field: &Option<String>,
+    message: &str,
+) -> String {
+    let mut out = String::new();
+    if let Some(path) = path {
+        out.push_str(&format!("{path}: "));
+    }
+    out.push_str(&format!("parse error at line {line} column {column}"));
+    if let Some(field) = field {
+        out.push_str(&format!(" in \"{field}\""));
+    }
+    out.push_str(&format!(": {message}"));
+    out
+}
+
+/// turns a serde error for `text` into [`FunscriptError::Parse`]
+fn parse_error(text: &str, err: SerdeError) -> FunscriptError {
+    let (line, column) = (err.line(), err.column());
+    let full = err.to_string();
+    let message = match full.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => full,
+    };
+    // serde names the field itself for missing and unknown fields
+    let named = message
+        .split('`')
+        .nth(1)
+        .filter(|_| message.contains(" field `"));
+    let field = match named {
+        Some(name) => Some(name.to_string()),
+        None => key_before(text, line, column),
+    };
+    FunscriptError::Parse {
+        path: None,
+        line,
+        column,
+        field,
+        message,
+    }
+}
+
+/// the last object key that starts before `line`:`column` in `text`
+fn key_before(text: &str, line: usize, column: usize) -> Option<String> {
+    let offset: usize = text
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>()
+        + column;
+    let (mut in_string, mut escaped) = (false, false);
+    let (mut start, mut last_string, mut key) = (0, None, None);
+    for (i, c) in text.char_indices().take_while(|&(i, _)| i < offset) {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => {
+                    in_string = false;
+                    last_string = Some(&text[start..i]);
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                start = i + 1;
+            }
+            ':' => key = last_string.map(str::to_string),
+            c if !c.is_whitespace() => last_string = None,
+            _ => {}
+        }
+    }
+    key
 }
 
 /// loads a .funscript file using the provided path
+/// duplicated top level keys are rejected instead of letting one silently win
 pub fn load_funscript(path: &str) -> Result<FScript, FunscriptError> {
+    let (script, _) = load_funscript_with(path, ParseMode::Strict)?;
+    Ok(script)
+}
+
+/// loads a .funscript file, tolerating missing, unknown and mistyped fields
+/// anything that had to be dropped is reported as a warning
+pub fn load_funscript_lenient(path: &str) -> Result<(FScript, Vec<LoadWarning>), FunscriptError> {
+    load_funscript_with(path, ParseMode::Lenient)
+}
+
+/// loads a .funscript file using the given parse mode
+pub fn load_funscript_with(
+    path: &str,
+    mode: ParseMode,
+) -> Result<(FScript, Vec<LoadWarning>), FunscriptError> {
     let file = std::fs::read_to_string(path)?;
-    let json = serde_json::from_str::<FScript>(&file)?;
-    Ok(json)
+    parse_funscript(&file, mode).map_err(|err| match err {
+        FunscriptError::Parse {
+            path: None,
+            line,
+            column,
+            field,
+            message,
+        } => FunscriptError::Parse {
+            path: Some(path.to_string()),
+            line,
+            column,
+            field,
+            message,
+        },
+        err => err,
+    })
+}
+
+/// parses .funscript json text
+pub(crate) fn parse_funscript(
+    text: &str,
+    mode: ParseMode,
+) -> Result<(FScript, Vec<LoadWarning>), FunscriptError> {
+    let duplicates = find_duplicate_keys(text);
+    if mode == ParseMode::Strict {
+        if let Some((key, first_offset, second_offset)) = duplicates.into_iter().next() {
+            return Err(FunscriptError::DuplicateKey {
+                key,
+                first_offset,
+                second_offset,
+            });
+        }
+        let script = serde_json::from_str::<FScript>(text).map_err(|err| parse_error(text, err))?;
+        return Ok((script, Vec::new()));
+    }
+
+    // serde_json keeps the last occurrence of a duplicated key
+    let value: Value = serde_json::from_str(text).map_err(|err| parse_error(text, err))?;
+    let (script, warnings) = FScript::from_value(value, mode)?;
+    let mut all: Vec<LoadWarning> = duplicates
+        .into_iter()
+        .map(|(key, first, second)| LoadWarning {
+            field: key,
+            message: format!("duplicate key at byte {first} and {second}, keeping the last"),
+        })
+        .collect();
+    all.extend(warnings);
+    Ok((script, all))
+}
+
+/// finds top level keys that appear more than once in a json object
+/// returns the key with the byte offsets of its first and repeated occurrence
+pub(crate) fn find_duplicate_keys(text: &str) -> Vec<(String, usize, usize)> {
+    let skip_ws = |pos: usize| pos + (text[pos..].len() - text[pos..].trim_start().len());
+    let mut seen: Vec<(String, usize)> = Vec::new();
+    let mut duplicates = Vec::new();
+
+    let mut pos = skip_ws(0);
+    if !text[pos..].starts_with('{') {
+        return duplicates;
+    }
+    pos += 1;
+    loop {
+        pos = skip_ws(pos);
+        match text[pos..].chars().next() {
+            Some(',') => {
+                pos += 1;
+                continue;
+            }
+            Some('"') => {}
+            _ => break,
+        }
+
+        let start = pos;
+        let mut keys = serde_json::Deserializer::from_str(&text[pos..]).into_iter::<String>();
+        let Some(Ok(key)) = keys.next() else { break };
+        pos = skip_ws(pos + keys.byte_offset());
+        if !text[pos..].starts_with(':') {
+            break;
+        }
+        pos += 1;
+        let mut values =
+            serde_json::Deserializer::from_str(&text[pos..]).into_iter::<serde::de::IgnoredAny>();
+        let Some(Ok(_)) = values.next() else { break };
+        pos += values.byte_offset();
+
+        match seen.iter().find(|(k, _)| *k == key) {
+            Some((_, first)) => duplicates.push((key, *first, start)),
+            None => seen.push((key, start)),
+        }
+    }
+    duplicates
+}
+
+/// deserializes every top level field on its own, dropping the ones that don't fit
+fn from_value_lenient(value: Value) -> Result<(FScript, Vec<LoadWarning>), FunscriptError> {
+    let Value::Object(fields) = value else {
+        return Err(<SerdeError as serde::de::Error>::custom("expected a json object").into());
+    };
+    let mut warnings = Vec::new();
+    let mut good = Map::new();
+    for (key, value) in fields {
+        let value = match key.as_str() {
+            "metadata" => metadata_lenient(value, &mut warnings)?,
+            "actions" | "rawActions" => points_lenient(&key, value, &mut warnings),
+            _ => value,
+        };
+        let mut probe = Map::new();
+        probe.insert(key.clone(), value);
+        let probe = Value::Object(probe);
+        match FScript::deserialize(&probe) {
+            Ok(_) => {
+                if let Value::Object(probe) = probe {
+                    good.extend(probe);
+                }
+            }
+            Err(err) => warnings.push(LoadWarning {
+                field: key,
+                message: err.to_string(),
+            }),
+        }
+    }
+    Ok((serde_json::from_value(Value::Object(good))?, warnings))
+}
+
+/// fills missing metadata fields with defaults and drops mistyped ones
+fn metadata_lenient(
+    value: Value,
+    warnings: &mut Vec<LoadWarning>,
+) -> Result<Value, FunscriptError> {
+    let Value::Object(mut merged) = serde_json::to_value(OFSMetadata::default())? else {
+        unreachable!("metadata always serializes to an object");
+    };
+    let Value::Object(fields) = value else {
+        warnings.push(LoadWarning {
+            field: "metadata".to_string(),
+            message: "expected a json object".to_string(),
+        });
+        return Ok(Value::Object(merged));
+    };
+    for (key, value) in fields {
+        let mut probe = merged.clone();
+        probe.insert(key.clone(), value);
+        let probe = Value::Object(probe);
+        match OFSMetadata::deserialize(&probe) {
+            Ok(_) => {
+                if let Value::Object(probe) = probe {
+                    merged = probe;
+                }
+            }
+            Err(err) => warnings.push(LoadWarning {
+                field: format!("metadata.{key}"),
+                message: err.to_string(),
+            }),
+        }
+    }
+    Ok(Value::Object(merged))
+}
+
+/// coerces mistyped action points and skips the ones that can't be read
+/// floats are rounded, numeric strings parsed and unknown keys dropped
+fn points_lenient(field: &str, value: Value, warnings: &mut Vec<LoadWarning>) -> Value {
+    let Value::Array(entries) = value else {
+        // left for the field level check to report
+        return value;
+    };
+    let number = |v: &Value| {
+        match v {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        }
+        .filter(|n| n.is_finite() && n.abs() <= i32::MAX as f64)
+    };
+
+    let mut points = Vec::with_capacity(entries.len());
+    for (idx, entry) in entries.into_iter().enumerate() {
+        let mut warn = |message: String| {
+            warnings.push(LoadWarning {
+                field: format!("{field}[{idx}]"),
+                message,
+            })
+        };
+        let Value::Object(entry) = entry else {
+            warn("expected an object, skipped".to_string());
+            continue;
+        };
+        let at = entry.get("at").and_then(number);
+        let pos = entry.get("pos").and_then(number);
+        let (Some(at), Some(pos)) = (at, pos) else {
+            warn("missing or unreadable at/pos, skipped".to_string());
+            continue;
+        };
+        let exact = |v: &Value| v.as_i64().is_some_and(|n| i32::try_from(n).is_ok());
+        if !exact(&entry["at"]) || !exact(&entry["pos"]) {
+            warn(format!("coerced to at {} pos {}", at.round(), pos.round()));
+        }
+        if entry.len() > 2 {
+            warn("unknown keys dropped".to_string());
+        }
+        points.push(serde_json::json!({ "at": at.round() as i32, "pos": pos.round() as i32 }));
+    }
+    Value::Array(points)
 }
 
 /// saves a .funscript file using the provided path
 pub fn save_funscript(path: &str, script: &FScript) -> Result<(), FunscriptError> {
-    if !path.ends_with(".funscript") {
-        return Err(FunscriptError::FileReadError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "invalid file extension",
-        )));
+    save_funscript_with(path, script, &SaveOptions::default())
+}
+
+/// saves a .funscript file, formatted according to `options`
+pub fn save_funscript_with(
+    path: &str,
+    script: &FScript,
+    options: &SaveOptions,
+) -> Result<(), FunscriptError> {
+    check_extension(path, ".funscript")?;
+
+    std::fs::write(path, script.to_string_with(options)?)?;
+    Ok(())
+}
+
+/// saves a .funscript file without ever leaving a half written file at `path`
+/// the script is written to `<path>.tmp`, synced and renamed over the old file
+/// with `keep_backup` the previous version is kept as `<path>.bak`
+pub fn save_funscript_atomic(
+    path: &str,
+    script: &FScript,
+    keep_backup: bool,
+) -> Result<(), FunscriptError> {
+    check_extension(path, ".funscript")?;
+
+    let json = script.to_string_pretty()?;
+    let tmp_path = format!("{path}.tmp");
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+        if keep_backup && std::path::Path::new(path).exists() {
+            std::fs::copy(path, format!("{path}.bak"))?;
+        }
+        std::fs::rename(&tmp_path, path)
+    };
+    if let Err(err) = write() {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(err.into());
     }
+    Ok(())
+}
 
-    let json = serde_json::to_string_pretty(script)?;
-    std::fs::write(path, json)?;
+/// fails with [`FunscriptError::InvalidExtension`] unless `path` ends with `expected`
+pub(crate) fn check_extension(path: &str, expected: &'static str) -> Result<(), FunscriptError> {
+    if !path.ends_with(expected) {
+        return Err(FunscriptError::InvalidExtension {
+            path: path.to_string(),
+            expected,
+        });
+    }
     Ok(())
 }
 
-/// adds an action point
+/// the action point at `idx`
 pub fn get_pt(script: &mut FScript, idx: usize) -> Result<&mut FSPoint, FunscriptError> {
     if idx >= script.actions.len() {
         return Err(FunscriptError::PointError("get".to_string(), idx));
@@ -154,6 +928,64 @@ pub fn get_pt(script: &mut FScript, idx: usize) -> Result<&mut FSPoint, Funscrip
     Ok(&mut script.actions[idx])
 }
 
+/// adds an action point, keeping the actions sorted by time
+/// an existing point at the same time is overwritten, returns the index of the point
+pub fn insert_pt(script: &mut FScript, at: i32, pos: i32) -> usize {
+    let idx = script.actions.partition_point(|pt| pt.at < at);
+    match script.actions.get_mut(idx) {
+        Some(pt) if pt.at == at => pt.pos = pos,
+        _ => script.actions.insert(idx, FSPoint { pos, at }),
+    }
+    idx
+}
+
+/// removes and returns the action point at `idx`
+pub fn remove_pt(script: &mut FScript, idx: usize) -> Result<FSPoint, FunscriptError> {
+    if idx >= script.actions.len() {
+        return Err(FunscriptError::PointError("remove".to_string(), idx));
+    }
+    Ok(script.actions.remove(idx))
+}
+
+/// removes every action point with `start_ms <= at <= end_ms`, returns how many were removed
+pub fn remove_range(script: &mut FScript, start_ms: i64, end_ms: i64) -> usize {
+    let start = script
+        .actions
+        .partition_point(|pt| (pt.at as i64) < start_ms);
+    let end = script
+        .actions
+        .partition_point(|pt| (pt.at as i64) <= end_ms);
+    script.actions.drain(start..end.max(start)).len()
+}
+
+/// moves the action point at `idx` to a new time and position
+/// fails if it would pass or land on one of its neighbours
+pub fn move_pt(
+    script: &mut FScript,
+    idx: usize,
+    new_at: i32,
+    new_pos: i32,
+) -> Result<(), FunscriptError> {
+    if idx >= script.actions.len() {
+        return Err(FunscriptError::PointError("move".to_string(), idx));
+    }
+    if idx > 0 && script.actions[idx - 1].at >= new_at {
+        return Err(FunscriptError::NonMonotonicTimestamps { index: idx });
+    }
+    if script
+        .actions
+        .get(idx + 1)
+        .is_some_and(|pt| pt.at <= new_at)
+    {
+        return Err(FunscriptError::NonMonotonicTimestamps { index: idx + 1 });
+    }
+    script.actions[idx] = FSPoint {
+        pos: new_pos,
+        at: new_at,
+    };
+    Ok(())
+}
+
 /// runs the ramer-douglas-peucker algorithm on the script
 /// applies a smooth point reduction to the script by the given epsilon
 /// epsilon > ~10.0 will result in nothing but peaks and valleys
@@ -196,11 +1028,17 @@ pub fn print_script(script: &FScript) {
 mod tests {
     use super::*;
 
+    /// makes sure the output directory used by the save tests exists
+    fn out_dir() {
+        std::fs::create_dir_all("./test-scripts/out").unwrap();
+    }
+
     #[test]
     fn test_jfs_save_load_funscript() {
         let path = "./test-scripts/joyfunscripter.funscript";
         let save_path = "./test-scripts/out/joyfunscripter.funscript";
 
+        out_dir();
         let mut s = load_funscript(path).unwrap();
         assert!(s.last_position == 6388388382, "file has defaulted");
         s.bookmark = 100000;
@@ -214,6 +1052,7 @@ mod tests {
         let path = "./test-scripts/openfunscripter.funscript";
         let save_path = "./test-scripts/out/openfunscripter.funscript";
 
+        out_dir();
         let mut s = load_funscript(path).unwrap();
         assert!(s.metadata.duration == 2610, "file has defaulted");
         s.bookmark = 100000;
@@ -222,6 +1061,421 @@ mod tests {
         assert_eq!(check.bookmark, 100000);
     }
 
+    #[test]
+    fn test_parse_from_memory() {
+        let path = "./test-scripts/openfunscripter.funscript";
+        let bytes = std::fs::read(path).unwrap();
+        let from_slice = FScript::from_slice(&bytes).unwrap();
+        let from_reader = FScript::from_reader(std::fs::File::open(path).unwrap()).unwrap();
+        let from_str: FScript = std::str::from_utf8(&bytes).unwrap().parse().unwrap();
+        assert_eq!(from_slice.actions.len(), 3585);
+        assert_eq!(from_reader.actions.len(), 3585);
+        assert_eq!(from_str.metadata.duration, 2610);
+
+        assert!(FScript::from_slice(&[0xff, 0xfe]).is_err());
+        let dup = r#"{"version": "1.0", "version": "1.1"}"#;
+        assert!(matches!(
+            dup.parse::<FScript>(),
+            Err(FunscriptError::DuplicateKey { .. })
+        ));
+    }
+
+    #[test]
+    fn test_serialize_to_memory() {
+        let s = load_funscript("./test-scripts/scriptai-january-2022.funscript").unwrap();
+        let pretty = s.to_string_pretty().unwrap();
+        let compact = s.to_string_compact().unwrap();
+        assert!(pretty.contains('\n'));
+        assert!(!compact.contains('\n'));
+        assert!(compact.len() < pretty.len());
+        assert_eq!(s.to_vec().unwrap(), pretty.as_bytes());
+
+        let mut out = Vec::new();
+        s.to_writer(&mut out).unwrap();
+        let check = FScript::from_slice(&out).unwrap();
+        assert_eq!(check.actions.len(), 68);
+        assert_eq!(compact.parse::<FScript>().unwrap().actions.len(), 68);
+    }
+
+    #[test]
+    fn test_save_options() {
+        out_dir();
+        let path = "./test-scripts/joyfunscripter.funscript";
+        let save_path = "./test-scripts/out/joyfunscripter.minimal.funscript";
+        let s = load_funscript(path).unwrap();
+
+        save_funscript_with(save_path, &s, &SaveOptions::minimal()).unwrap();
+        let text = std::fs::read_to_string(save_path).unwrap();
+        assert!(!text.contains('\n'));
+        assert!(!text.contains("rawActions"));
+        assert!(text.contains("\"actions\""));
+        let check = load_funscript(save_path).unwrap();
+        assert_eq!(check.actions.len(), s.actions.len());
+        assert!(check.raw_actions.is_empty());
+        assert_eq!(check.last_position, s.last_position);
+
+        let empty = FScript::default();
+        assert_eq!(
+            empty.to_string_with(&SaveOptions::minimal()).unwrap(),
+            r#"{"actions":[]}"#
+        );
+        let kept = SaveOptions {
+            strip_raw_actions: true,
+            ..Default::default()
+        };
+        assert!(s
+            .to_string_with(&kept)
+            .unwrap()
+            .contains("\"rawActions\": []"));
+
+        let value = s.to_value_with(&kept).unwrap();
+        assert_eq!(value["rawActions"], Value::Array(Vec::new()));
+        assert_eq!(
+            serde_json::to_string_pretty(&value).unwrap(),
+            s.to_string_with(&kept).unwrap()
+        );
+        let value = s.to_value_with(&SaveOptions::minimal()).unwrap();
+        assert!(value.get("rawActions").is_none());
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            s.to_string_with(&SaveOptions::minimal()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_save_funscript_atomic() {
+        out_dir();
+        let save_path = "./test-scripts/out/atomic.funscript";
+        let _ = std::fs::remove_file(format!("{save_path}.bak"));
+        let mut s = load_funscript("./test-scripts/scriptai-january-2022.funscript").unwrap();
+
+        save_funscript_atomic(save_path, &s, true).unwrap();
+        s.actions.truncate(10);
+        save_funscript_atomic(save_path, &s, true).unwrap();
+        assert_eq!(load_funscript(save_path).unwrap().actions.len(), 10);
+        let backup = load_funscript(&format!("{save_path}.bak")).unwrap();
+        assert_eq!(backup.actions.len(), 68);
+        assert!(!std::path::Path::new(&format!("{save_path}.tmp")).exists());
+
+        assert!(matches!(
+            save_funscript_atomic("./test-scripts/out/atomic.json", &s, false),
+            Err(FunscriptError::InvalidExtension {
+                expected: ".funscript",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let text = "{\n  \"version\": \"1.0\",\n  \"actions\": [\n    {\"at\": 100, \"pos\": \"high\"}\n  ]\n}";
+        let Err(FunscriptError::Parse {
+            line, field, path, ..
+        }) = text.parse::<FScript>()
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(line, 4);
+        assert_eq!(field.as_deref(), Some("pos"));
+        assert_eq!(path, None);
+
+        let err = "{\"actions\": [{\"at\": 1, \"pos\": 2, \"extra\": 3}]}"
+            .parse::<FScript>()
+            .unwrap_err();
+        assert!(err.to_string().contains("in \"extra\""), "{err}");
+
+        out_dir();
+        let path = "./test-scripts/out/broken.funscript";
+        std::fs::write(path, "{\"version\": \"1.0\",").unwrap();
+        let err = load_funscript(path).unwrap_err();
+        assert!(err.to_string().starts_with(path), "{err}");
+        assert!(matches!(err, FunscriptError::Parse { line: 1, .. }));
+    }
+
+    #[test]
+    fn test_common_traits() {
+        let s = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        let copy = s.clone();
+        assert_eq!(copy, s);
+        let mut edited = s.clone();
+        edited.actions[0].pos += 1;
+        assert_ne!(edited, s);
+
+        let a = FSPoint { at: 100, pos: 90 };
+        let b = FSPoint { at: 200, pos: 10 };
+        assert!(a < b);
+        assert!(FSPoint { at: 100, pos: 95 } > a);
+        let set: std::collections::HashSet<FSPoint> = [a.clone(), a.clone(), b].into();
+        assert_eq!(set.len(), 2);
+        let mut sorted = [FSPoint { at: 300, pos: 0 }, a.clone()];
+        sorted.sort();
+        assert_eq!(sorted[0], a);
+    }
+
+    #[test]
+    fn test_edit_points() {
+        let mut s = FScript::default();
+        assert_eq!(insert_pt(&mut s, 200, 20), 0);
+        assert_eq!(insert_pt(&mut s, 0, 0), 0);
+        assert_eq!(insert_pt(&mut s, 100, 10), 1);
+        assert_eq!(insert_pt(&mut s, 300, 30), 3);
+        assert_eq!(insert_pt(&mut s, 100, 15), 1);
+        let times: Vec<_> = s.actions.iter().map(|pt| pt.at).collect();
+        assert_eq!(times, vec![0, 100, 200, 300]);
+        assert_eq!(s.actions[1].pos, 15);
+
+        assert!(move_pt(&mut s, 1, 150, 50).is_ok());
+        assert!(matches!(
+            move_pt(&mut s, 1, 200, 50),
+            Err(FunscriptError::NonMonotonicTimestamps { index: 2 })
+        ));
+        assert!(matches!(
+            move_pt(&mut s, 1, 0, 50),
+            Err(FunscriptError::NonMonotonicTimestamps { index: 1 })
+        ));
+        assert!(move_pt(&mut s, 4, 500, 0).is_err());
+
+        assert_eq!(remove_pt(&mut s, 0).unwrap().at, 0);
+        assert!(remove_pt(&mut s, 3).is_err());
+        assert_eq!(remove_range(&mut s, 150, 200), 2);
+        assert_eq!(s.actions.len(), 1);
+        assert_eq!(remove_range(&mut s, 400, 500), 0);
+    }
+
+    #[test]
+    fn test_segments() {
+        let s = load_funscript("./test-scripts/scriptai-january-2022.funscript").unwrap();
+        assert_eq!(s.segments().count(), 67);
+        let first = s.segments().next().unwrap();
+        assert_eq!(first.start.at, s.actions[0].at);
+        assert_eq!(first.delta(), s.actions[1].pos - s.actions[0].pos);
+
+        let mut s = FScript::default();
+        insert_pt(&mut s, 0, 0);
+        insert_pt(&mut s, 500, 100);
+        let segment = s.segments().next().unwrap();
+        assert_eq!(segment.duration_ms(), 500);
+        assert_eq!(segment.speed(), 200.0);
+        assert!(FScript::default().segments().next().is_none());
+    }
+
+    #[test]
+    fn test_duplicate_keys_strict() {
+        for path in [
+            "./test-scripts/duplicate-actions.funscript",
+            "./test-scripts/duplicate-actions-reversed.funscript",
+        ] {
+            match load_funscript(path) {
+                Err(FunscriptError::DuplicateKey {
+                    key,
+                    first_offset,
+                    second_offset,
+                }) => {
+                    assert_eq!(key, "actions");
+                    assert!(first_offset < second_offset);
+                }
+                other => panic!("expected duplicate key error, got {:?}", other),
+            }
+        }
+        assert!(find_duplicate_keys(
+            &std::fs::read_to_string("./test-scripts/openfunscripter.funscript").unwrap()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_from_value_embedded() {
+        let path = "./test-scripts/openfunscripter.funscript";
+        let fixture = std::fs::read_to_string(path).unwrap();
+        let wrapper = format!(
+            r#"{{ "scenes": [ {{ "name": "intro", "script": {} }} ] }}"#,
+            fixture
+        );
+        let mut doc: Value = serde_json::from_str(&wrapper).unwrap();
+        let embedded = doc["scenes"][0]["script"].take();
+
+        let (s, _) = FScript::from_value(embedded, ParseMode::Strict).unwrap();
+        let check = load_funscript(path).unwrap();
+        assert_eq!(s.actions.len(), 3585);
+        assert_eq!(s.to_value().unwrap(), check.to_value().unwrap());
+    }
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        out_dir();
+        let path = "./test-scripts/out/unknown-fields.funscript";
+        let text = r#"{
+            "version": "1.0",
+            "actions": [ { "at": 0, "pos": 10 } ],
+            "myTool": { "fps": 60 },
+            "simulatorPresets": [ {
+                "name": "s", "fullRange": false, "direction": 1, "rotation": 0.0,
+                "length": 1.0, "width": 1.0, "offset": "", "color": "Lime", "glow": true
+            } ],
+            "metadata": {
+                "bookmarks": [], "chapters": [], "creator": "", "description": "",
+                "duration": 1, "license": "", "notes": "", "performers": [],
+                "script_url": "", "tags": [], "title": "", "type": "", "video_url": "",
+                "rating": 5
+            }
+        }"#;
+        std::fs::write(path, text).unwrap();
+        let s = load_funscript(path).unwrap();
+        assert_eq!(s.extra["myTool"]["fps"], 60);
+        assert_eq!(s.simulator_presets[0].extra["glow"], true);
+        assert_eq!(s.metadata.extra["rating"], 5);
+
+        save_funscript(path, &s).unwrap();
+        let check = load_funscript(path).unwrap();
+        assert_eq!(check.extra, s.extra);
+        assert_eq!(
+            check.simulator_presets[0].extra,
+            s.simulator_presets[0].extra
+        );
+        assert_eq!(check.metadata.extra, s.metadata.extra);
+    }
+
+    #[test]
+    fn test_lenient_load() {
+        out_dir();
+        let path = "./test-scripts/out/lenient.funscript";
+        let text = r#"{
+            "version": "1.0",
+            "inverted": "no",
+            "range": "100",
+            "actions": [ { "at": 0, "pos": 10 }, { "at": 100, "pos": 90 } ],
+            "metadata": { "title": "hello", "chapters": [ { "name": "intro" } ] }
+        }"#;
+        std::fs::write(path, text).unwrap();
+        assert!(load_funscript(path).is_err());
+
+        let (s, warnings) = load_funscript_lenient(path).unwrap();
+        assert_eq!(s.actions.len(), 2);
+        assert_eq!(s.range, -1);
+        assert!(!s.inverted);
+        assert_eq!(s.metadata.title, "hello");
+        assert_eq!(s.metadata.duration, -1);
+        let fields: Vec<_> = warnings.iter().map(|w| w.field.as_str()).collect();
+        assert_eq!(fields, vec!["inverted", "metadata.chapters", "range"]);
+
+        let (s, warnings) =
+            load_funscript_lenient("./test-scripts/scriptai-january-2022.funscript").unwrap();
+        assert_eq!(s.actions.len(), 68);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_lenient_skips_bad_actions() {
+        let text = r#"{
+            "actions": [
+                { "at": 0, "pos": 10 },
+                { "at": 100.6, "pos": "90" },
+                { "at": 200, "pos": "high" },
+                7,
+                { "at": 300, "pos": 20, "type": "step" }
+            ]
+        }"#;
+        let (s, warnings) = parse_funscript(text, ParseMode::Lenient).unwrap();
+        let points: Vec<_> = s.actions.iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(points, vec![(0, 10), (101, 90), (300, 20)]);
+        let fields: Vec<_> = warnings.iter().map(|w| w.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["actions[1]", "actions[2]", "actions[3]", "actions[4]"]
+        );
+        assert!(parse_funscript(text, ParseMode::Strict).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_keys_lenient() {
+        let (s, warnings) =
+            load_funscript_lenient("./test-scripts/duplicate-actions.funscript").unwrap();
+        assert_eq!(s.actions.len(), 3);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field, "actions");
+
+        let (s, warnings) =
+            load_funscript_lenient("./test-scripts/duplicate-actions-reversed.funscript").unwrap();
+        assert!(s.actions.is_empty());
+        assert_eq!(warnings[0].field, "actions");
+    }
+
+    #[test]
+    fn test_structured_chapters() {
+        out_dir();
+        let path = "./test-scripts/out/chapters.funscript";
+        let mut s = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        s.metadata.chapters = vec![
+            Chapter {
+                name: "intro".to_string(),
+                start_time: 0,
+                end_time: 218_703,
+            },
+            Chapter {
+                name: "main".to_string(),
+                start_time: 218_703,
+                end_time: 3_723_004,
+            },
+        ];
+        save_funscript(path, &s).unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        assert!(text.contains(r#""endTime": "01:02:03.004""#));
+
+        let check = load_funscript(path).unwrap();
+        assert_eq!(check.metadata.chapters(), s.metadata.chapters());
+        assert_eq!(check.metadata.chapter_at(1000).unwrap().name, "intro");
+        assert_eq!(check.metadata.chapter_at(218_703).unwrap().name, "main");
+        assert!(check.metadata.chapter_at(4_000_000).is_none());
+    }
+
+    #[test]
+    fn test_chapter_formats() {
+        let chapters: Vec<Chapter> = serde_json::from_str(
+            r#"[
+                { "name": "a", "startTime": "00:00:01.500", "endTime": "02:03.25" },
+                { "name": "b", "startTime": 5000, "endTime": 6000 },
+                "legacy"
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            (chapters[0].start_time, chapters[0].end_time),
+            (1500, 123_250)
+        );
+        assert_eq!((chapters[1].start_time, chapters[1].end_time), (5000, 6000));
+        assert_eq!(chapters[2].name, "legacy");
+        assert!(serde_json::from_str::<Chapter>(
+            r#"{ "name": "bad", "startTime": "soon", "endTime": 0 }"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_pos_at() {
+        let s = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        // { "at": 218703, "pos": 53 }, { "at": 218870, "pos": 54 }, { "at": 219254, "pos": 19 }
+        assert_eq!(s.pos_at(218703), Some(53));
+        assert_eq!(s.pos_at(219254), Some(19));
+        assert_eq!(s.pos_at(219062), Some(37));
+        assert_eq!(s.pos_at(0), Some(53));
+        assert_eq!(
+            s.pos_at(i64::MAX),
+            Some(s.actions.last().unwrap().pos as u8)
+        );
+        assert_eq!(FScript::default().pos_at(0), None);
+    }
+
+    #[test]
+    fn test_actions_in_range() {
+        let s = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        let window = s.actions_in_range(218870, 219337);
+        let times: Vec<_> = window.iter().map(|pt| pt.at).collect();
+        assert_eq!(times, vec![218870, 219254, 219337]);
+        assert_eq!(s.actions_in_range(218871, 219253).len(), 0);
+        assert_eq!(s.actions_in_range(0, i64::MAX).len(), s.actions.len());
+        assert!(s.actions_in_range(300_000, 0).is_empty());
+    }
+
     #[test]
     fn test_get_set_pt() {
         let path = "./test-scripts/openfunscripter.funscript";
@@ -0,0 +1,54 @@
+use serde_json::{json, Value};
+
+use crate::FScript;
+
+/// the script in the plain `at,pos` csv accepted by The Handy's script upload
+/// the `inverted` flag is applied, positions are clamped and actions before 0 dropped
+/// since the device has no way to play them
+pub fn to_handy_csv(script: &FScript) -> String {
+    let mut csv = String::with_capacity(script.actions.len() * 10);
+    let mut last_at = None;
+    for pt in script.resolved_actions() {
+        // the device rejects scripts that go back in time
+        if pt.at < 0 || last_at.is_some_and(|last| pt.at <= last) {
+            continue;
+        }
+        last_at = Some(pt.at);
+        csv.push_str(&format!("{},{}\n", pt.at, pt.pos.clamp(0, 100)));
+    }
+    csv
+}
+
+/// request body for the HSSP setup call, which tells the device where to download
+/// an uploaded script from, the sha256 of the file lets it skip downloads it already has
+pub fn hssp_setup_body(url: &str, sha256: Option<&str>) -> Value {
+    match sha256 {
+        Some(hash) => json!({ "url": url, "sha256": hash }),
+        None => json!({ "url": url }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FSPoint;
+
+    #[test]
+    fn test_to_handy_csv() {
+        let mut s = FScript::default();
+        for (at, pos) in [(-50, 10), (0, 20), (100, 120), (100, 0), (250, 75)] {
+            s.actions.push(FSPoint { at, pos });
+        }
+        assert_eq!(to_handy_csv(&s), "0,20\n100,100\n250,75\n");
+        s.inverted = true;
+        assert_eq!(to_handy_csv(&s), "0,80\n100,0\n250,25\n");
+    }
+
+    #[test]
+    fn test_hssp_setup_body() {
+        let body = hssp_setup_body("https://example.com/s.csv", Some("abc"));
+        assert_eq!(body["url"], "https://example.com/s.csv");
+        assert_eq!(body["sha256"], "abc");
+        assert!(hssp_setup_body("u", None).get("sha256").is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use std::io::BufRead;
+
+use serde_json::Value;
+
+use crate::{FSPoint, FScript, FunscriptError};
+
+/// imports a Fleshlight Launch era json script
+/// accepts a bare array of `{at, pos}` actions, the same array under an `actions` or
+/// `data` key, `{time, position}` pairs with the time in seconds, and the Kiiroo
+/// `{"text": "{1.50:4,2.00:1}"}` form whose 0-4 positions are scaled to 0-100
+pub fn import_launch_json(text: &str) -> Result<FScript, FunscriptError> {
+    let value: Value = serde_json::from_str(text).map_err(|err| crate::parse_error(text, err))?;
+    let mut script = FScript::default();
+    if let Some(Value::String(subtitles)) = value.get("text") {
+        script.actions = kiiroo_text(subtitles)?;
+    } else {
+        let list = match value.as_array() {
+            Some(list) => Some(("", list)),
+            None => ["actions", "data"]
+                .into_iter()
+                .find_map(|key| Some((key, value.get(key)?.as_array()?))),
+        };
+        let Some((key, list)) = list else {
+            return Err(FunscriptError::Parse {
+                path: None,
+                line: 0,
+                column: 0,
+                field: None,
+                message: "no action list found".to_string(),
+            });
+        };
+        for (idx, item) in list.iter().enumerate() {
+            let number = |key: &str| item.get(key).and_then(Value::as_f64);
+            let point = match (number("at"), number("pos")) {
+                (Some(at), Some(pos)) => Some((at, pos)),
+                _ => number("time")
+                    .zip(number("position"))
+                    .map(|(t, p)| (t * 1000.0, p)),
+            };
+            let Some((at, pos)) = point else {
+                return Err(FunscriptError::Parse {
+                    path: None,
+                    line: 0,
+                    column: 0,
+                    field: Some(format!("{key}[{idx}]")),
+                    message: "expected at and pos or time and position".to_string(),
+                });
+            };
+            script.actions.push(FSPoint {
+                at: at.round() as i32,
+                pos: pos.round().clamp(0.0, 100.0) as i32,
+            });
+        }
+    }
+    script.actions.sort_by_key(|pt| pt.at);
+    Ok(script)
+}
+
+/// imports a RealTouch era text script with a time in seconds and a value per line
+/// values run from 0 to `value_max` and are scaled to 0-100, the separator may be
+/// whitespace, a comma or a semicolon and `#` or `;` lines are comments
+pub fn import_ott(reader: impl BufRead, value_max: f64) -> Result<FScript, FunscriptError> {
+    let mut script = FScript::default();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        let mut cells = line
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+            .filter(|c| !c.is_empty())
+            .map(str::parse::<f64>);
+        match (cells.next(), cells.next()) {
+            (Some(Ok(time)), Some(Ok(value))) if time.is_finite() && value.is_finite() => {
+                script.actions.push(FSPoint {
+                    at: (time * 1000.0).round() as i32,
+                    pos: (value / value_max * 100.0).round().clamp(0.0, 100.0) as i32,
+                });
+            }
+            _ => {
+                return Err(FunscriptError::Parse {
+                    path: None,
+                    line: idx + 1,
+                    column: 0,
+                    field: None,
+                    message: format!("expected time and value but got \"{line}\""),
+                })
+            }
+        }
+    }
+    script.actions.sort_by_key(|pt| pt.at);
+    Ok(script)
+}
+
+/// parses the `{seconds:position,...}` kiiroo subtitle text
+fn kiiroo_text(text: &str) -> Result<Vec<FSPoint>, FunscriptError> {
+    let body = text.trim().trim_start_matches('{').trim_end_matches('}');
+    let mut actions = Vec::new();
+    for entry in body.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let parsed = entry.split_once(':').and_then(|(t, p)| {
+            Some((t.trim().parse::<f64>().ok()?, p.trim().parse::<f64>().ok()?))
+        });
+        let Some((time, level)) = parsed else {
+            return Err(FunscriptError::Parse {
+                path: None,
+                line: 0,
+                column: 0,
+                field: Some("text".to_string()),
+                message: format!("bad kiiroo entry \"{entry}\""),
+            });
+        };
+        actions.push(FSPoint {
+            at: (time * 1000.0).round() as i32,
+            pos: (level * 25.0).round().clamp(0.0, 100.0) as i32,
+        });
+    }
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(s: &FScript) -> Vec<(i32, i32)> {
+        s.actions.iter().map(|pt| (pt.at, pt.pos)).collect()
+    }
+
+    #[test]
+    fn test_import_launch_json() {
+        let bare = import_launch_json(r#"[{"at": 500, "pos": 90}, {"at": 0, "pos": 10}]"#).unwrap();
+        assert_eq!(points(&bare), vec![(0, 10), (500, 90)]);
+
+        let timed = import_launch_json(r#"{"data": [{"time": 1.25, "position": 40}]}"#).unwrap();
+        assert_eq!(points(&timed), vec![(1250, 40)]);
+
+        let kiiroo = import_launch_json(r#"{"text": "{0.50:4,1.00:0,1.75:2}"}"#).unwrap();
+        assert_eq!(points(&kiiroo), vec![(500, 100), (1000, 0), (1750, 50)]);
+
+        let err = import_launch_json(r#"{"actions": [{"at": 0, "pos": 0}, {"at": 0}]}"#);
+        assert!(
+            matches!(&err, Err(FunscriptError::Parse { field: Some(f), .. }) if f == "actions[1]"),
+            "{err:?}"
+        );
+        assert!(import_launch_json(r#"{"text": "{1.0:x}"}"#).is_err());
+        assert!(import_launch_json(r#"{"version": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_import_ott() {
+        let text = "; exported\n0.0 0\n0.5\t255\n1.25,127.5\n";
+        let s = import_ott(text.as_bytes(), 255.0).unwrap();
+        assert_eq!(points(&s), vec![(0, 0), (500, 100), (1250, 50)]);
+
+        let err = import_ott("0.0 0\nend\n".as_bytes(), 100.0).unwrap_err();
+        assert!(
+            matches!(err, FunscriptError::Parse { line: 2, .. }),
+            "{err}"
+        );
+    }
+}
@@ -1,7 +1,4 @@
-#![feature(assert_matches)]
-mod funscript;
-
-use crate::funscript::*;
+// use funscript::*;
 // use native_dialog::FileDialog;
 
 fn main() {
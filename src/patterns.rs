@@ -0,0 +1,186 @@
+use std::f64::consts::TAU;
+
+use crate::FSPoint;
+
+/// shape and timing of a generated pattern
+/// the points start at 0, offset them to place the pattern in a script
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatternParams {
+    pub duration_ms: i64,
+    /// cycles per second
+    pub frequency_hz: f64,
+    /// distance between the lowest and highest position
+    pub amplitude: f64,
+    /// where in the cycle the pattern starts, 0.0 to 1.0
+    pub phase: f64,
+    /// position the pattern moves around
+    pub center: f64,
+    /// time between generated points
+    pub step_ms: i64,
+}
+
+impl Default for PatternParams {
+    fn default() -> Self {
+        Self {
+            duration_ms: 10_000,
+            frequency_hz: 1.0,
+            amplitude: 100.0,
+            phase: 0.0,
+            center: 50.0,
+            step_ms: 50,
+        }
+    }
+}
+
+/// a smooth wave starting at the top of the cycle
+pub fn sine(params: &PatternParams) -> Vec<FSPoint> {
+    wave(params, |cycle| (cycle * TAU).cos())
+}
+
+/// straight strokes between the top and bottom, starting at the top
+pub fn triangle(params: &PatternParams) -> Vec<FSPoint> {
+    wave(params, |cycle| 4.0 * (cycle - 0.5).abs() - 1.0)
+}
+
+/// slow rise from the bottom to the top followed by a fast drop
+pub fn sawtooth(params: &PatternParams) -> Vec<FSPoint> {
+    wave(params, |cycle| cycle * 2.0 - 1.0)
+}
+
+/// alternates between holding at the top and the bottom
+pub fn square(params: &PatternParams) -> Vec<FSPoint> {
+    wave(params, |cycle| if cycle < 0.5 { 1.0 } else { -1.0 })
+}
+
+/// a single linear move from `from` to `to` over `duration_ms`
+pub fn ramp(duration_ms: i64, from: i32, to: i32) -> Vec<FSPoint> {
+    vec![
+        FSPoint {
+            pos: from.clamp(0, 100),
+            at: 0,
+        },
+        FSPoint {
+            pos: to.clamp(0, 100),
+            at: duration_ms.max(1) as i32,
+        },
+    ]
+}
+
+/// a random walk from `center`, the same seed always gives the same points
+/// `frequency_hz` steps are taken per second, each moving up to `amplitude / 2`
+pub fn random_walk(params: &PatternParams, seed: u64) -> Vec<FSPoint> {
+    // splitmix64, unlike xorshift every seed including 0 gives a usable sequence
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    let step = if params.frequency_hz > 0.0 {
+        (1000.0 / params.frequency_hz).round().max(1.0) as i64
+    } else {
+        params.duration_ms.max(1)
+    };
+    let mut pos = params.center.clamp(0.0, 100.0);
+    let mut points = Vec::new();
+    let mut t = 0;
+    while t <= params.duration_ms {
+        points.push(point(t, pos));
+        pos = (pos + (next() * 2.0 - 1.0) * params.amplitude / 2.0).clamp(0.0, 100.0);
+        t += step;
+    }
+    points
+}
+
+/// samples a periodic shape, `shape` maps the position in the cycle to -1.0..=1.0
+fn wave(params: &PatternParams, shape: impl Fn(f64) -> f64) -> Vec<FSPoint> {
+    let step = params.step_ms.max(1);
+    let mut points = Vec::with_capacity((params.duration_ms / step + 2).max(0) as usize);
+    let at = |t: i64| {
+        let cycle = (t as f64 / 1000.0 * params.frequency_hz + params.phase).rem_euclid(1.0);
+        params.center + shape(cycle) * params.amplitude / 2.0
+    };
+    let mut t = 0;
+    while t <= params.duration_ms {
+        points.push(point(t, at(t)));
+        t += step;
+    }
+    if t - step < params.duration_ms {
+        points.push(point(params.duration_ms, at(params.duration_ms)));
+    }
+    points
+}
+
+fn point(t: i64, pos: f64) -> FSPoint {
+    FSPoint {
+        pos: pos.round().clamp(0.0, 100.0) as i32,
+        at: t as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(points: &[FSPoint]) -> Vec<i32> {
+        points.iter().map(|pt| pt.pos).collect()
+    }
+
+    #[test]
+    fn test_periodic_patterns() {
+        let params = PatternParams {
+            duration_ms: 1000,
+            step_ms: 250,
+            ..Default::default()
+        };
+        assert_eq!(positions(&sine(&params)), vec![100, 50, 0, 50, 100]);
+        assert_eq!(positions(&triangle(&params)), vec![100, 50, 0, 50, 100]);
+        assert_eq!(positions(&sawtooth(&params)), vec![0, 25, 50, 75, 0]);
+        assert_eq!(positions(&square(&params)), vec![100, 100, 0, 0, 100]);
+
+        let shifted = PatternParams {
+            phase: 0.5,
+            amplitude: 40.0,
+            center: 30.0,
+            ..params
+        };
+        assert_eq!(positions(&sine(&shifted)), vec![10, 30, 50, 30, 10]);
+
+        let times: Vec<_> = sine(&PatternParams {
+            duration_ms: 110,
+            ..params
+        })
+        .iter()
+        .map(|pt| pt.at)
+        .collect();
+        assert_eq!(times, vec![0, 110]);
+    }
+
+    #[test]
+    fn test_ramp_and_random_walk() {
+        let points = ramp(500, 10, 120);
+        assert_eq!((points[1].at, points[1].pos), (500, 100));
+
+        let params = PatternParams {
+            frequency_hz: 4.0,
+            amplitude: 20.0,
+            ..Default::default()
+        };
+        let a = random_walk(&params, 7);
+        assert_eq!(a.len(), 41);
+        assert_eq!(a[0].pos, 50);
+        assert!(a.windows(2).all(|w| (w[1].pos - w[0].pos).abs() <= 11));
+        assert_eq!(positions(&a), positions(&random_walk(&params, 7)));
+        assert_ne!(positions(&a), positions(&random_walk(&params, 8)));
+
+        // no seed leaves the generator stuck at zero
+        for seed in [0, 0x9e37_79b9_7f4a_7c15] {
+            let walk = random_walk(&params, seed);
+            assert!(walk.iter().any(|pt| pt.pos != 50), "stuck with seed {seed}");
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{interpolate, FSPoint, FScript, FunscriptError};
+
+/// a single interpolated position emitted by the [`Player`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tick {
+    /// playback time in ms
+    pub time_ms: i64,
+    /// interpolated position, 0.0 to 100.0
+    pub pos: f64,
+}
+
+/// receives ticks from a running [`Player`]
+/// returning an error stops playback and the error is handed back by `stop`/`wait`
+pub trait Sink: Send + 'static {
+    fn send(&mut self, tick: Tick) -> Result<(), FunscriptError>;
+}
+
+impl<F: FnMut(Tick) + Send + 'static> Sink for F {
+    fn send(&mut self, tick: Tick) -> Result<(), FunscriptError> {
+        self(tick);
+        Ok(())
+    }
+}
+
+impl Sink for Sender<Tick> {
+    fn send(&mut self, tick: Tick) -> Result<(), FunscriptError> {
+        Sender::send(self, tick)
+            .map_err(|_| FunscriptError::PlayerError("receiver disconnected".to_string()))
+    }
+}
+
+struct State {
+    stop: bool,
+    /// wall clock time at which playback was at `origin_ms`
+    origin: Instant,
+    origin_ms: i64,
+    /// playback time the player is frozen at while paused
+    paused_at: Option<i64>,
+    /// playback speed multiplier, 1.0 is real time
+    rate: f64,
+    /// cap on emitted movement in units per real second
+    max_speed: Option<f64>,
+}
+
+impl State {
+    fn time_ms(&self) -> i64 {
+        match self.paused_at {
+            Some(ms) => ms,
+            None => {
+                self.origin_ms + (self.origin.elapsed().as_secs_f64() * 1000.0 * self.rate) as i64
+            }
+        }
+    }
+
+    fn set_time(&mut self, ms: i64) {
+        self.origin = Instant::now();
+        self.origin_ms = ms;
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+/// plays a script in real time, sending interpolated positions to a [`Sink`]
+pub struct Player {
+    script: Arc<FScript>,
+    /// the actions as played, with `inverted` applied
+    track: Arc<Vec<FSPoint>>,
+    tick: Duration,
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<Result<(), FunscriptError>>>,
+}
+
+impl Player {
+    /// creates a player that emits `tick_rate_hz` positions per second
+    pub fn new(script: FScript, tick_rate_hz: f64) -> Result<Player, FunscriptError> {
+        if script.actions.is_empty() {
+            return Err(FunscriptError::EmptyScript);
+        }
+        if !(tick_rate_hz > 0.0 && tick_rate_hz <= 1000.0) {
+            return Err(FunscriptError::PlayerError(format!(
+                "tick rate {tick_rate_hz} is outside 0-1000hz"
+            )));
+        }
+        Ok(Player {
+            track: Arc::new(script.resolved_actions()),
+            script: Arc::new(script),
+            tick: Duration::from_secs_f64(1.0 / tick_rate_hz),
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    stop: false,
+                    origin: Instant::now(),
+                    origin_ms: 0,
+                    paused_at: None,
+                    rate: 1.0,
+                    max_speed: None,
+                }),
+                wake: Condvar::new(),
+            }),
+            handle: None,
+        })
+    }
+
+    /// the script being played
+    pub fn script(&self) -> &FScript {
+        &self.script
+    }
+
+    /// true while the playback thread is alive
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(|h| !h.is_finished())
+    }
+
+    /// current playback time in ms
+    pub fn position_ms(&self) -> i64 {
+        lock(&self.shared).map_or(0, |state| state.time_ms())
+    }
+
+    /// true while playback is paused
+    pub fn is_paused(&self) -> bool {
+        lock(&self.shared).is_ok_and(|state| state.paused_at.is_some())
+    }
+
+    /// freezes playback at the current time, no ticks are sent until `resume`
+    pub fn pause(&self) -> Result<(), FunscriptError> {
+        let mut state = lock(&self.shared)?;
+        if state.paused_at.is_none() {
+            state.paused_at = Some(state.time_ms());
+        }
+        Ok(())
+    }
+
+    /// continues playback from where it was paused
+    pub fn resume(&self) -> Result<(), FunscriptError> {
+        let mut state = lock(&self.shared)?;
+        if let Some(ms) = state.paused_at.take() {
+            state.set_time(ms);
+        }
+        self.shared.wake.notify_all();
+        Ok(())
+    }
+
+    /// jumps playback to `ms`, keeping the paused state
+    pub fn seek(&self, ms: i64) -> Result<(), FunscriptError> {
+        let mut state = lock(&self.shared)?;
+        let ms = ms.max(0);
+        match state.paused_at {
+            Some(_) => state.paused_at = Some(ms),
+            None => state.set_time(ms),
+        }
+        self.shared.wake.notify_all();
+        Ok(())
+    }
+
+    /// changes the playback speed, e.g. 0.5 for half speed video
+    pub fn set_rate(&self, rate: f32) -> Result<(), FunscriptError> {
+        if !(rate > 0.0 && rate <= 16.0) {
+            return Err(FunscriptError::PlayerError(format!(
+                "playback rate {rate} is outside 0-16x"
+            )));
+        }
+        let mut state = lock(&self.shared)?;
+        // rebase the clock so the time doesn't jump
+        let now = state.time_ms();
+        state.set_time(now);
+        state.rate = rate as f64;
+        Ok(())
+    }
+
+    /// the current playback speed multiplier
+    pub fn rate(&self) -> f32 {
+        lock(&self.shared).map_or(1.0, |state| state.rate as f32)
+    }
+
+    /// limits how fast emitted positions may move, in units per real second
+    /// useful when a faster rate would push strokes beyond what a device can do
+    pub fn set_speed_cap(&self, max_speed: Option<f64>) -> Result<(), FunscriptError> {
+        lock(&self.shared)?.max_speed = max_speed;
+        Ok(())
+    }
+
+    /// starts playback from the beginning on a background thread
+    /// once the end of the script is reached the thread exits and `start` can be called again
+    pub fn start(&mut self, mut sink: impl Sink) -> Result<(), FunscriptError> {
+        if self.is_running() {
+            return Err(FunscriptError::PlayerError("already running".to_string()));
+        }
+        // collect the result of a previous run that finished on its own
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        {
+            let mut state = lock(&self.shared)?;
+            state.stop = false;
+            state.paused_at = None;
+            state.set_time(0);
+        }
+
+        let track = Arc::clone(&self.track);
+        let shared = Arc::clone(&self.shared);
+        let tick = self.tick;
+        let end = track.last().map_or(0, |pt| pt.at as i64);
+        self.handle = Some(std::thread::spawn(move || {
+            let mut last: Option<(Instant, f64)> = None;
+            let mut state = lock(&shared)?;
+            loop {
+                if state.stop {
+                    return Ok(());
+                }
+                if state.paused_at.is_some() {
+                    state = shared.wake.wait(state).map_err(|_| poisoned())?;
+                    continue;
+                }
+                let time_ms = state.time_ms();
+                let mut pos = interpolate(&track, time_ms).unwrap_or(0.0);
+                if let (Some(max_speed), Some((at, prev))) = (state.max_speed, last) {
+                    let step = max_speed * at.elapsed().as_secs_f64();
+                    pos = pos.clamp(prev - step, prev + step);
+                }
+                last = Some((Instant::now(), pos));
+                drop(state);
+                sink.send(Tick { time_ms, pos })?;
+                if time_ms >= end {
+                    return Ok(());
+                }
+                state = lock(&shared)?;
+                state = shared
+                    .wake
+                    .wait_timeout(state, tick)
+                    .map_err(|_| poisoned())?
+                    .0;
+            }
+        }));
+        Ok(())
+    }
+
+    /// stops playback and waits for the thread to exit
+    /// returns the error that ended playback early, if any
+    pub fn stop(&mut self) -> Result<(), FunscriptError> {
+        lock(&self.shared)?.stop = true;
+        self.shared.wake.notify_all();
+        self.join()
+    }
+
+    /// blocks until playback reaches the end of the script
+    pub fn wait(&mut self) -> Result<(), FunscriptError> {
+        self.join()
+    }
+
+    fn join(&mut self) -> Result<(), FunscriptError> {
+        match self.handle.take() {
+            Some(handle) => handle
+                .join()
+                .map_err(|_| FunscriptError::PlayerError("playback thread panicked".to_string()))?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn lock(shared: &Shared) -> Result<std::sync::MutexGuard<'_, State>, FunscriptError> {
+    shared.state.lock().map_err(|_| poisoned())
+}
+
+fn poisoned() -> FunscriptError {
+    FunscriptError::PlayerError("player state poisoned".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{channel, TryRecvError};
+
+    fn ramp(end_ms: i32) -> FScript {
+        let mut s = FScript::default();
+        s.actions.push(FSPoint { at: 0, pos: 0 });
+        s.actions.push(FSPoint {
+            at: end_ms,
+            pos: 100,
+        });
+        s
+    }
+
+    #[test]
+    fn test_player_plays_to_end() {
+        let mut player = Player::new(ramp(200), 100.0).unwrap();
+        let (tx, rx) = channel();
+        player.start(tx).unwrap();
+        player.wait().unwrap();
+
+        let ticks: Vec<Tick> = rx.try_iter().collect();
+        assert!(ticks.len() >= 5, "only {} ticks", ticks.len());
+        assert!(ticks.windows(2).all(|w| w[0].time_ms <= w[1].time_ms));
+        for tick in &ticks {
+            let expected = (tick.time_ms as f64 / 2.0).min(100.0);
+            assert!((tick.pos - expected).abs() < 1e-9);
+        }
+        assert_eq!(ticks.last().unwrap().pos, 100.0);
+        assert!(!player.is_running());
+    }
+
+    #[test]
+    fn test_player_inverted() {
+        let mut script = ramp(200);
+        script.inverted = true;
+        let mut player = Player::new(script, 100.0).unwrap();
+        let (tx, rx) = channel();
+        player.start(tx).unwrap();
+        player.wait().unwrap();
+
+        let ticks: Vec<Tick> = rx.try_iter().collect();
+        for tick in &ticks {
+            let expected = 100.0 - (tick.time_ms as f64 / 2.0).min(100.0);
+            assert!((tick.pos - expected).abs() < 1e-9);
+        }
+        assert_eq!(ticks.last().unwrap().pos, 0.0);
+        assert_eq!(player.script().actions[1].pos, 100);
+    }
+
+    #[test]
+    fn test_player_stop_and_errors() {
+        let (tx, rx) = channel();
+        let mut player = Player::new(ramp(10_000), 100.0).unwrap();
+        player.start(tx).unwrap();
+        assert!(player.start(|_| {}).is_err());
+        rx.recv().unwrap();
+        player.stop().unwrap();
+        // the thread is gone and took the sender with it
+        rx.try_iter().for_each(drop);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+        // a dropped receiver ends playback with an error
+        let (tx, rx) = channel();
+        drop(rx);
+        player.start(tx).unwrap();
+        assert!(player.wait().is_err());
+
+        assert!(Player::new(FScript::default(), 60.0).is_err());
+    }
+
+    #[test]
+    fn test_player_rate() {
+        let (tx, rx) = channel();
+        let mut player = Player::new(ramp(100_000), 200.0).unwrap();
+        assert!(player.set_rate(0.0).is_err());
+        player.set_rate(2.0).unwrap();
+        let started = Instant::now();
+        player.start(tx).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        let at = player.position_ms();
+        let real = started.elapsed().as_secs_f64() * 1000.0;
+        assert!(at >= 199 && at as f64 <= real * 2.0 + 1.0, "{at}");
+
+        player.set_rate(0.5).unwrap();
+        assert_eq!(player.rate(), 0.5);
+        let measured = Instant::now();
+        let at = player.position_ms();
+        std::thread::sleep(Duration::from_millis(100));
+        let later = player.position_ms();
+        let real = measured.elapsed().as_secs_f64() * 1000.0;
+        let played = later - at;
+        assert!(
+            played >= 49 && played as f64 <= real * 0.5 + 1.0,
+            "{played}"
+        );
+        player.stop().unwrap();
+        rx.try_iter().for_each(drop);
+    }
+
+    #[test]
+    fn test_player_speed_cap() {
+        // the ramp needs 1000 units/s, capped to 100 units/s
+        let (tx, rx) = channel();
+        let mut player = Player::new(ramp(100), 200.0).unwrap();
+        player.set_speed_cap(Some(100.0)).unwrap();
+        player.start(tx).unwrap();
+        player.wait().unwrap();
+        let last = rx.try_iter().last().unwrap();
+        assert!(last.pos < 50.0, "{}", last.pos);
+    }
+
+    #[test]
+    fn test_player_pause_resume_seek() {
+        let (tx, rx) = channel();
+        let mut player = Player::new(ramp(10_000), 200.0).unwrap();
+        player.start(tx).unwrap();
+        std::thread::sleep(Duration::from_millis(30));
+
+        player.pause().unwrap();
+        assert!(player.is_paused());
+        let frozen = player.position_ms();
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(player.position_ms(), frozen);
+        // nothing is sent while paused, only what was on its way before
+        let late: Vec<Tick> = rx.try_iter().collect();
+        assert!(late.iter().all(|t| t.time_ms <= frozen));
+        assert!(late.iter().filter(|t| t.time_ms == frozen).count() <= 1);
+
+        player.seek(5000).unwrap();
+        assert_eq!(player.position_ms(), 5000);
+        let resumed = Instant::now();
+        player.resume().unwrap();
+        // a tick computed just before the pause may still turn up
+        let tick = rx.iter().find(|t| t.time_ms > frozen).unwrap();
+        let elapsed = resumed.elapsed().as_millis() as i64;
+        assert!(
+            tick.time_ms >= 5000 && tick.time_ms <= 5000 + elapsed,
+            "{}",
+            tick.time_ms
+        );
+        assert!((tick.pos - tick.time_ms as f64 / 100.0).abs() < 1e-9);
+
+        player.seek(9990).unwrap();
+        player.wait().unwrap();
+        assert_eq!(rx.try_iter().last().unwrap().pos, 100.0);
+        assert!(Player::new(ramp(10), 0.0).is_err());
+    }
+}
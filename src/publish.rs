@@ -0,0 +1,166 @@
+use crate::FScript;
+
+/// what [`FScript::clean_for_publish`] should strip besides the editor state
+#[derive(Debug, Clone, Copy)]
+pub struct CleanOptions {
+    /// also clear the bookmark and the metadata bookmarks
+    pub strip_bookmarks: bool,
+    /// remove local file paths from video_url and notes
+    pub strip_local_paths: bool,
+}
+
+impl Default for CleanOptions {
+    fn default() -> Self {
+        Self {
+            strip_bookmarks: false,
+            strip_local_paths: true,
+        }
+    }
+}
+
+/// summary of what was removed by [`FScript::clean_for_publish`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CleanSummary {
+    pub raw_actions_removed: usize,
+    pub simulator_presets_removed: usize,
+    pub clips_removed: usize,
+    pub bookmarks_removed: usize,
+    pub editor_fields_reset: usize,
+    pub extra_fields_removed: usize,
+    pub paths_removed: Vec<String>,
+}
+
+impl FScript {
+    /// strips private and editor specific data so the script can be shared
+    /// actions, chapters and the public metadata are kept
+    pub fn clean_for_publish(&mut self, options: CleanOptions) -> CleanSummary {
+        let mut summary = CleanSummary {
+            raw_actions_removed: self.raw_actions.len(),
+            simulator_presets_removed: self.simulator_presets.len(),
+            clips_removed: self.clips.len(),
+            ..Default::default()
+        };
+        self.raw_actions.clear();
+        self.simulator_presets.clear();
+        self.clips.clear();
+
+        // unknown fields may hold anything a tool decided to store
+        summary.extra_fields_removed = self.extra.len() + self.metadata.extra.len();
+        self.extra.clear();
+        self.metadata.extra.clear();
+
+        let unset = FScript::default();
+        let mut reset = |changed: bool| summary.editor_fields_reset += changed as usize;
+        reset(self.last_position != unset.last_position);
+        reset(self.graph_duration != unset.graph_duration);
+        reset(self.speed_ratio != unset.speed_ratio);
+        reset(self.injection_speed != unset.injection_speed);
+        reset(self.injection_bias != unset.injection_bias);
+        reset(self.scripting_mode != unset.scripting_mode);
+        reset(self.active_simulator != unset.active_simulator);
+        reset(self.reduction_tolerance != unset.reduction_tolerance);
+        reset(self.reduction_stretch != unset.reduction_stretch);
+        self.last_position = unset.last_position;
+        self.graph_duration = unset.graph_duration;
+        self.speed_ratio = unset.speed_ratio;
+        self.injection_speed = unset.injection_speed;
+        self.injection_bias = unset.injection_bias;
+        self.scripting_mode = unset.scripting_mode;
+        self.active_simulator = unset.active_simulator;
+        self.reduction_tolerance = unset.reduction_tolerance;
+        self.reduction_stretch = unset.reduction_stretch;
+
+        if options.strip_bookmarks {
+            summary.bookmarks_removed = self.metadata.bookmarks.len();
+            if self.bookmark != unset.bookmark {
+                summary.bookmarks_removed += 1;
+            }
+            self.bookmark = unset.bookmark;
+            self.metadata.bookmarks.clear();
+        }
+
+        if options.strip_local_paths {
+            if is_local_path(&self.metadata.video_url) {
+                summary
+                    .paths_removed
+                    .push(std::mem::take(&mut self.metadata.video_url));
+            }
+            let mut kept = Vec::new();
+            for word in self.metadata.notes.split(' ') {
+                if is_local_path(word) {
+                    summary.paths_removed.push(word.to_string());
+                } else {
+                    kept.push(word);
+                }
+            }
+            self.metadata.notes = kept.join(" ");
+        }
+
+        summary
+    }
+}
+
+/// true for absolute unix/windows paths and file:// urls
+fn is_local_path(s: &str) -> bool {
+    let s = s.trim();
+    let bytes = s.as_bytes();
+    let windows_drive = bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    windows_drive
+        || s.starts_with("file://")
+        || s.starts_with("\\\\")
+        || s.starts_with('/')
+        || s.starts_with("~/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_funscript, save_funscript};
+
+    #[test]
+    fn test_clean_for_publish_jfs() {
+        std::fs::create_dir_all("./test-scripts/out").unwrap();
+        let path = "./test-scripts/joyfunscripter.funscript";
+        let save_path = "./test-scripts/out/joyfunscripter.clean.funscript";
+
+        let mut s = load_funscript(path).unwrap();
+        let actions = s.actions.len();
+        s.metadata.video_url = "C:\\Users\\me\\Videos\\scene.mp4".to_string();
+        s.metadata.notes = "synced to /home/me/scene.mp4 by hand".to_string();
+        s.extra
+            .insert("localCache".to_string(), "/tmp/cache".into());
+
+        let summary = s.clean_for_publish(CleanOptions::default());
+        assert_eq!(summary.raw_actions_removed, 4709);
+        assert_eq!(summary.simulator_presets_removed, 1);
+        assert_eq!(summary.paths_removed.len(), 2);
+        assert_eq!(summary.extra_fields_removed, 1);
+        assert!(s.extra.is_empty());
+        assert_eq!(s.metadata.notes, "synced to by hand");
+        assert_eq!(s.actions.len(), actions);
+        assert_eq!(s.last_position, -1);
+        assert_eq!(s.bookmark, 100000);
+
+        save_funscript(save_path, &s).unwrap();
+        let text = std::fs::read_to_string(save_path).unwrap();
+        assert!(text.contains("\"rawActions\": []"));
+        assert!(text.contains("\"simulatorPresets\": []"));
+        let check = load_funscript(save_path).unwrap();
+        assert_eq!(check.actions.len(), actions);
+    }
+
+    #[test]
+    fn test_clean_for_publish_bookmarks() {
+        let path = "./test-scripts/joyfunscripter.funscript";
+        let mut s = load_funscript(path).unwrap();
+        let summary = s.clean_for_publish(CleanOptions {
+            strip_bookmarks: true,
+            strip_local_paths: false,
+        });
+        assert_eq!(summary.bookmarks_removed, 1);
+        assert_eq!(s.bookmark, -1);
+    }
+}
@@ -0,0 +1,297 @@
+use std::io::BufRead;
+
+use crate::filters::moving_average;
+use crate::simplify::{simplify, Algorithm, SimplificationReport};
+use crate::{apply_rdp, FSPoint, FScript, FunscriptError};
+
+/// how [`import_raw_recording`] maps a capture log onto a script
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawImportOptions {
+    /// raw value that maps to position 0
+    pub min: f64,
+    /// raw value that maps to position 100
+    pub max: f64,
+    /// flip the mapping so `min` becomes 100
+    pub invert: bool,
+    /// drop samples closer together than this rate allows
+    pub max_rate_hz: Option<f64>,
+    /// when set, `actions` is filled by running rdp with this epsilon over `raw_actions`
+    pub simplify_epsilon: Option<f64>,
+}
+
+impl Default for RawImportOptions {
+    fn default() -> Self {
+        Self {
+            min: 0.0,
+            max: 100.0,
+            invert: false,
+            max_rate_hz: None,
+            simplify_epsilon: None,
+        }
+    }
+}
+
+/// warnings collected while importing a capture log
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RawImportReport {
+    /// rows that were read and mapped
+    pub rows_imported: usize,
+    /// rows with empty or non-numeric cells
+    pub rows_skipped: usize,
+    /// rows dropped by the rate limit
+    pub rows_downsampled: usize,
+    /// rows replaced by a later row with the same timestamp
+    pub rows_collapsed: usize,
+    /// rows were not in chronological order and had to be sorted
+    pub sorted: bool,
+}
+
+/// imports a `timestamp_ms,axis_value` csv capture log into `raw_actions`
+/// a first line without any numbers is treated as a header, rows with the same
+/// millisecond are collapsed into the one that came last
+pub fn import_raw_recording(
+    reader: impl BufRead,
+    options: RawImportOptions,
+) -> Result<(FScript, RawImportReport), FunscriptError> {
+    let mut report = RawImportReport::default();
+    let mut samples: Vec<(f64, f64)> = Vec::new();
+
+    let mut first = true;
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let header =
+            std::mem::take(&mut first) && line.split(',').all(|c| c.trim().parse::<f64>().is_err());
+        let mut cells = line.split(',').map(|c| c.trim().parse::<f64>());
+        match (cells.next(), cells.next()) {
+            (Some(Ok(at)), Some(Ok(value))) if at.is_finite() && value.is_finite() => {
+                samples.push((at, value));
+            }
+            _ if header => {}
+            _ => report.rows_skipped += 1,
+        }
+    }
+
+    if samples.windows(2).any(|w| w[1].0 < w[0].0) {
+        report.sorted = true;
+        // stable, so rows with the same time stay in file order
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+    let mut collapsed: Vec<(f64, f64)> = Vec::with_capacity(samples.len());
+    for sample in samples {
+        match collapsed.last_mut() {
+            Some(last) if last.0.round() == sample.0.round() => {
+                *last = sample;
+                report.rows_collapsed += 1;
+            }
+            _ => collapsed.push(sample),
+        }
+    }
+
+    let span = options.max - options.min;
+    let min_gap = options.max_rate_hz.map_or(0.0, |hz| 1000.0 / hz);
+    let mut script = FScript::default();
+    let mut last_at = f64::NEG_INFINITY;
+    for (at, value) in collapsed {
+        if at - last_at < min_gap {
+            report.rows_downsampled += 1;
+            continue;
+        }
+        last_at = at;
+
+        let mut pos = if span == 0.0 {
+            0.0
+        } else {
+            (value - options.min) / span * 100.0
+        };
+        if options.invert {
+            pos = 100.0 - pos;
+        }
+        script.raw_actions.push(FSPoint {
+            at: at.round() as i32,
+            pos: pos.round().clamp(0.0, 100.0) as i32,
+        });
+        report.rows_imported += 1;
+    }
+
+    if let Some(epsilon) = options.simplify_epsilon {
+        script.actions = script.raw_actions.clone();
+        apply_rdp(&mut script, epsilon);
+    }
+
+    Ok((script, report))
+}
+
+/// the steps [`bake_raw`] runs, in order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BakeOptions {
+    /// moving average window applied first, see [`moving_average`]
+    pub smoothing_window_ms: Option<i64>,
+    /// snap times to this grid, e.g. one video frame, later points on the same step win
+    pub time_step_ms: Option<i64>,
+    /// snap positions to multiples of this
+    pub position_step: Option<i32>,
+    pub simplify: Option<Algorithm>,
+}
+
+impl Default for BakeOptions {
+    /// close to what OFS does when simplifying a recording
+    fn default() -> Self {
+        Self {
+            smoothing_window_ms: Some(50),
+            time_step_ms: None,
+            position_step: None,
+            simplify: Some(Algorithm::PeakPreserving { epsilon: 1.5 }),
+        }
+    }
+}
+
+/// turns a recording in `raw_actions` into editable `actions`
+/// the raw actions are kept, any existing actions are replaced
+/// the report compares the new actions against the recording
+pub fn bake_raw(script: &mut FScript, options: BakeOptions) -> SimplificationReport {
+    let mut points = script.raw_actions.clone();
+
+    if let Some(window) = options.smoothing_window_ms {
+        moving_average(&mut points, window);
+    }
+    if let Some(step) = options.time_step_ms.filter(|&step| step > 1) {
+        for pt in points.iter_mut() {
+            pt.at = ((pt.at as f64 / step as f64).round() as i64 * step) as i32;
+        }
+        points.dedup_by(|later, earlier| {
+            let same = later.at == earlier.at;
+            if same {
+                earlier.pos = later.pos;
+            }
+            same
+        });
+    }
+    if let Some(step) = options.position_step.filter(|&step| step > 1) {
+        for pt in points.iter_mut() {
+            pt.pos = ((pt.pos as f64 / step as f64).round() as i32 * step).clamp(0, 100);
+        }
+    }
+
+    script.actions = points;
+    if let Some(algorithm) = options.simplify {
+        simplify(script, algorithm);
+    }
+    SimplificationReport::new(&script.raw_actions, &script.actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// two seconds of a 1hz sine recorded at 120hz from a joystick axis
+    fn log() -> String {
+        let mut csv = String::from("timestamp_ms,axis_value\n");
+        for i in 0..240 {
+            let at = i as f64 * 1000.0 / 120.0;
+            let phase = at / 1000.0 * std::f64::consts::TAU;
+            let value = phase.sin() * 32767.5 - 0.5;
+            csv.push_str(&format!("{:.1},{:.0}\n", at, value));
+        }
+        csv.push_str("2000.0,NaN\n2008.3,\n1500.0,0\n");
+        csv
+    }
+
+    fn joystick() -> RawImportOptions {
+        RawImportOptions {
+            min: -32768.0,
+            max: 32767.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_import_raw_mapping() {
+        let (s, report) = import_raw_recording(log().as_bytes(), joystick()).unwrap();
+        assert_eq!(report.rows_imported, 240);
+        assert_eq!(report.rows_skipped, 2);
+        assert_eq!(report.rows_collapsed, 1);
+        assert!(report.sorted);
+        assert!(s.actions.is_empty());
+
+        let min = s.raw_actions.iter().map(|p| p.pos).min().unwrap();
+        let max = s.raw_actions.iter().map(|p| p.pos).max().unwrap();
+        assert_eq!((min, max), (0, 100));
+        assert_eq!(s.raw_actions[0].pos, 50);
+        assert!(s.raw_actions.windows(2).all(|w| w[0].at <= w[1].at));
+
+        let options = RawImportOptions {
+            invert: true,
+            ..joystick()
+        };
+        let (inv, _) = import_raw_recording(log().as_bytes(), options).unwrap();
+        assert_eq!(inv.raw_actions[30].pos, 100 - s.raw_actions[30].pos);
+    }
+
+    #[test]
+    fn test_import_raw_header_and_duplicates() {
+        // a broken first row is a skipped row, not a header
+        let (s, report) =
+            import_raw_recording("12.5,abc\n0,50\n".as_bytes(), Default::default()).unwrap();
+        assert_eq!(report.rows_skipped, 1);
+        assert_eq!(s.raw_actions.len(), 1);
+
+        let (s, report) = import_raw_recording(log().as_bytes(), joystick()).unwrap();
+        let at_1500: Vec<_> = s.raw_actions.iter().filter(|pt| pt.at == 1500).collect();
+        assert_eq!(at_1500.len(), 1);
+        assert_eq!(report.rows_imported, s.raw_actions.len());
+        assert!(s.raw_actions.windows(2).all(|w| w[0].at < w[1].at));
+
+        // the later row wins, even when sorting moved it
+        let text = "at,pos\n100,20\n0,10\n100.2,90\n";
+        let (s, report) = import_raw_recording(text.as_bytes(), Default::default()).unwrap();
+        let points: Vec<_> = s.raw_actions.iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(points, vec![(0, 10), (100, 90)]);
+        assert_eq!(report.rows_collapsed, 1);
+    }
+
+    #[test]
+    fn test_import_raw_downsample_and_simplify() {
+        let options = RawImportOptions {
+            max_rate_hz: Some(40.0),
+            simplify_epsilon: Some(1.0),
+            ..joystick()
+        };
+        let (s, report) = import_raw_recording(log().as_bytes(), options).unwrap();
+        // every 3rd sample survives, 120hz / 3 = 40hz
+        assert_eq!(s.raw_actions.len(), 80);
+        assert_eq!(report.rows_downsampled, 240 - 80);
+        assert!(s.raw_actions.windows(2).all(|w| w[1].at - w[0].at == 25));
+        assert!(
+            s.actions.len() > 4 && s.actions.len() < 30,
+            "{}",
+            s.actions.len()
+        );
+    }
+
+    #[test]
+    fn test_bake_raw() {
+        let (mut s, _) = import_raw_recording(log().as_bytes(), joystick()).unwrap();
+        let report = bake_raw(&mut s, BakeOptions::default());
+        assert_eq!(s.raw_actions.len(), 240);
+        assert_eq!(report.original_points, 240);
+        assert!(s.actions.len() < 60, "{}", s.actions.len());
+        assert!(report.max_deviation < 10.0, "{report}");
+
+        let options = BakeOptions {
+            smoothing_window_ms: None,
+            time_step_ms: Some(50),
+            position_step: Some(10),
+            simplify: None,
+        };
+        bake_raw(&mut s, options);
+        assert_eq!(s.actions.len(), 41);
+        assert!(s
+            .actions
+            .iter()
+            .all(|pt| pt.at % 50 == 0 && pt.pos % 10 == 0));
+    }
+}
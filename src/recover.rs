@@ -0,0 +1,202 @@
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde_json::{Map, Value};
+
+use crate::{FSPoint, FScript, FunscriptError, ParseMode};
+
+/// what [`recover_funscript`] managed to salvage from a damaged file
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveryReport {
+    /// number of complete action points that were read
+    pub actions_recovered: usize,
+    /// top level fields (other than actions) that were complete
+    pub fields_recovered: Vec<String>,
+    /// byte offset where parsing gave up, `None` if the file parsed normally
+    pub stopped_at: Option<usize>,
+}
+
+/// loads a .funscript file, salvaging what it can if the json is truncated or corrupt
+/// complete action points and any top level fields before the damage are kept
+pub fn recover_funscript(path: &str) -> Result<(FScript, RecoveryReport), FunscriptError> {
+    let bytes = std::fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    let err = match crate::parse_funscript(&text, ParseMode::Strict) {
+        Ok((script, _)) => {
+            let report = RecoveryReport {
+                actions_recovered: script.actions.len(),
+                fields_recovered: Vec::new(),
+                stopped_at: None,
+            };
+            return Ok((script, report));
+        }
+        // only broken json is salvaged, a well formed file with bad fields is an error
+        Err(err) => match serde_json::from_str::<IgnoredAny>(&text) {
+            Err(syntax) if syntax.is_syntax() || syntax.is_eof() => syntax,
+            _ => return Err(err),
+        },
+    };
+
+    let mut fields = Map::new();
+    let mut actions = Vec::new();
+    let mut pos = skip_ws(&text, 0);
+    if !text[pos..].starts_with('{') {
+        return Err(crate::parse_error(&text, err));
+    }
+    pos += 1;
+
+    let stopped_at = loop {
+        pos = skip_ws(&text, pos);
+        match text[pos..].chars().next() {
+            Some('}') | None => break pos,
+            Some(',') => {
+                pos += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some((key, next)) = parse_one::<String>(&text, pos) else {
+            break pos;
+        };
+        pos = skip_ws(&text, next);
+        if !text[pos..].starts_with(':') {
+            break pos;
+        }
+        pos = skip_ws(&text, pos + 1);
+
+        if key == "actions" {
+            let (next, finished) = salvage_actions(&text, pos, &mut actions);
+            pos = next;
+            if !finished {
+                break pos;
+            }
+            continue;
+        }
+
+        // a value only counts if something follows it, otherwise
+        // a truncated number like `10` from `100` would be accepted
+        match parse_one::<Value>(&text, pos) {
+            Some((value, next)) if text[skip_ws(&text, next)..].starts_with([',', '}']) => {
+                fields.insert(key, value);
+                pos = next;
+            }
+            _ => break pos,
+        }
+    };
+
+    // keep only the fields that still deserialize into the script
+    let mut good = Map::new();
+    let mut fields_recovered = Vec::new();
+    for (key, value) in fields {
+        good.insert(key.clone(), value);
+        if serde_json::from_value::<FScript>(Value::Object(good.clone())).is_ok() {
+            fields_recovered.push(key);
+        } else {
+            good.remove(&key);
+        }
+    }
+
+    let mut script = serde_json::from_value::<FScript>(Value::Object(good))?;
+    script.actions = actions;
+    let report = RecoveryReport {
+        actions_recovered: script.actions.len(),
+        fields_recovered,
+        stopped_at: Some(stopped_at),
+    };
+    Ok((script, report))
+}
+
+/// reads action points from the array starting at `pos`
+/// returns where it stopped and whether the closing bracket was reached
+fn salvage_actions(text: &str, mut pos: usize, actions: &mut Vec<FSPoint>) -> (usize, bool) {
+    if !text[pos..].starts_with('[') {
+        return (pos, false);
+    }
+    pos += 1;
+    loop {
+        pos = skip_ws(text, pos);
+        match text[pos..].chars().next() {
+            Some(']') => return (pos + 1, true),
+            Some(',') => pos += 1,
+            Some('{') => match parse_one::<FSPoint>(text, pos) {
+                Some((pt, next)) => {
+                    actions.push(pt);
+                    pos = next;
+                }
+                None => return (pos, false),
+            },
+            _ => return (pos, false),
+        }
+    }
+}
+
+/// parses a single json value at `pos`, returning it and the offset after it
+fn parse_one<T: DeserializeOwned>(text: &str, pos: usize) -> Option<(T, usize)> {
+    let mut stream = serde_json::Deserializer::from_str(&text[pos..]).into_iter::<T>();
+    match stream.next() {
+        Some(Ok(value)) => Some((value, pos + stream.byte_offset())),
+        _ => None,
+    }
+}
+
+fn skip_ws(text: &str, pos: usize) -> usize {
+    let rest = &text[pos..];
+    pos + (rest.len() - rest.trim_start().len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recover_clean_file() {
+        let path = "./test-scripts/openfunscripter.funscript";
+        let (s, report) = recover_funscript(path).unwrap();
+        assert_eq!(s.actions.len(), 3585);
+        assert_eq!(report.stopped_at, None);
+    }
+
+    #[test]
+    fn test_recover_truncated() {
+        std::fs::create_dir_all("./test-scripts/out").unwrap();
+        let path = "./test-scripts/openfunscripter.funscript";
+        let save_path = "./test-scripts/out/openfunscripter.truncated.funscript";
+        let bytes = std::fs::read(path).unwrap();
+
+        let mut last = 0;
+        for cut in [40, 1000, 1013, 50_000, 100_000, bytes.len() - 2] {
+            std::fs::write(save_path, &bytes[..cut]).unwrap();
+            let (s, report) = recover_funscript(save_path).unwrap();
+            assert!(report.actions_recovered >= last, "not monotone at {cut}");
+            assert!(report.stopped_at.unwrap() <= cut);
+            assert_eq!(s.actions.len(), report.actions_recovered);
+            last = report.actions_recovered;
+        }
+        assert_eq!(last, 3585);
+    }
+
+    #[test]
+    fn test_recover_salvages_fields() {
+        std::fs::create_dir_all("./test-scripts/out").unwrap();
+        let save_path = "./test-scripts/out/partial.funscript";
+        let text = r#"{ "version": "1.0", "range": 90, "actions": [ { "at": 0, "pos": 10 }, { "at": 100, "pos": 9"#;
+        std::fs::write(save_path, text).unwrap();
+        let (s, report) = recover_funscript(save_path).unwrap();
+        assert_eq!(s.version, "1.0");
+        assert_eq!(s.range, 90);
+        assert_eq!(report.actions_recovered, 1);
+        assert_eq!(report.fields_recovered.len(), 2);
+    }
+
+    #[test]
+    fn test_recover_rejects_duplicate_keys() {
+        std::fs::create_dir_all("./test-scripts/out").unwrap();
+        let save_path = "./test-scripts/out/duplicate.funscript";
+        let text = r#"{ "actions": [ { "at": 0, "pos": 10 } ], "actions": [] }"#;
+        std::fs::write(save_path, text).unwrap();
+        assert!(matches!(
+            recover_funscript(save_path),
+            Err(FunscriptError::DuplicateKey { .. })
+        ));
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Write;
+
+use crate::analysis::heatmap;
+use crate::{check_extension, FScript, FunscriptError};
+
+/// size of the images written by [`render_heatmap`]
+pub const HEATMAP_WIDTH: u32 = 1200;
+pub const HEATMAP_HEIGHT: u32 = 40;
+
+/// writes the speed heatmap of a script as a colored timeline bar to an .svg file
+pub fn render_heatmap(script: &FScript, path: &str) -> Result<(), FunscriptError> {
+    check_extension(path, ".svg")?;
+    std::fs::write(path, heatmap_svg(script))?;
+    Ok(())
+}
+
+/// writes the position curve of a script to an .svg file
+pub fn render_curve(
+    script: &FScript,
+    path: &str,
+    width: u32,
+    height: u32,
+) -> Result<(), FunscriptError> {
+    check_extension(path, ".svg")?;
+    std::fs::write(path, curve_svg(script, width, height))?;
+    Ok(())
+}
+
+/// the svg document written by [`render_heatmap`]
+pub fn heatmap_svg(script: &FScript) -> String {
+    let (width, height) = (HEATMAP_WIDTH, HEATMAP_HEIGHT);
+    let mut svg = svg_header(width, height);
+    let last = script.actions.last().map_or(0, |pt| pt.at as i64);
+    // two pixels per bucket keeps the bar readable for short scripts
+    let bucket_ms = (last * 2 / width as i64).max(1);
+    let buckets = heatmap(script, bucket_ms);
+    let step = width as f64 / buckets.len().max(1) as f64;
+    for (i, value) in buckets.iter().enumerate() {
+        let _ = write!(
+            svg,
+            r#"<rect x="{:.2}" y="0" width="{:.2}" height="{height}" fill="{}"/>"#,
+            i as f64 * step,
+            step + 0.5,
+            heat_color(*value)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// the svg document written by [`render_curve`]
+pub fn curve_svg(script: &FScript, width: u32, height: u32) -> String {
+    let mut svg = svg_header(width, height);
+    let (Some(first), Some(last)) = (script.actions.first(), script.actions.last()) else {
+        svg.push_str("</svg>\n");
+        return svg;
+    };
+    let span = (last.at - first.at).max(1) as f64;
+    let mut points = String::new();
+    for pt in &script.actions {
+        let x = (pt.at - first.at) as f64 / span * width as f64;
+        let y = (100 - pt.pos.clamp(0, 100)) as f64 / 100.0 * height as f64;
+        let _ = write!(points, "{x:.1},{y:.1} ");
+    }
+    let _ = write!(
+        svg,
+        r##"<polyline fill="none" stroke="#e04080" stroke-width="1" points="{}"/>"##,
+        points.trim_end()
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn svg_header(width: u32, height: u32) -> String {
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><rect width="100%" height="100%" fill="#000000"/>"##
+    )
+}
+
+/// black through blue, green, yellow to red, like the usual script timeline bars
+fn heat_color(value: f32) -> String {
+    const STOPS: [(f32, [u8; 3]); 5] = [
+        (0.0, [0, 0, 0]),
+        (0.25, [30, 144, 255]),
+        (0.5, [0, 220, 0]),
+        (0.75, [255, 220, 0]),
+        (1.0, [255, 0, 0]),
+    ];
+    let value = value.clamp(0.0, 1.0);
+    let idx = STOPS
+        .iter()
+        .rposition(|(edge, _)| value >= *edge)
+        .unwrap_or(0);
+    let (lo, lo_rgb) = STOPS[idx];
+    let (hi, hi_rgb) = STOPS[(idx + 1).min(STOPS.len() - 1)];
+    let frac = if hi > lo {
+        (value - lo) / (hi - lo)
+    } else {
+        0.0
+    };
+    let channel =
+        |i: usize| (lo_rgb[i] as f32 + (hi_rgb[i] as f32 - lo_rgb[i] as f32) * frac) as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(0), channel(1), channel(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_funscript;
+
+    #[test]
+    fn test_render() {
+        std::fs::create_dir_all("./test-scripts/out").unwrap();
+        let s = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+
+        let path = "./test-scripts/out/openfunscripter.heatmap.svg";
+        render_heatmap(&s, path).unwrap();
+        let svg = std::fs::read_to_string(path).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.matches("<rect").count() > 500);
+
+        let path = "./test-scripts/out/openfunscripter.curve.svg";
+        render_curve(&s, path, 800, 200).unwrap();
+        let svg = std::fs::read_to_string(path).unwrap();
+        assert!(svg.contains(r#"width="800""#));
+        assert!(svg.contains("<polyline"));
+        assert!(render_curve(&s, "./test-scripts/out/curve.png", 10, 10).is_err());
+    }
+
+    #[test]
+    fn test_heat_color() {
+        assert_eq!(heat_color(0.0), "#000000");
+        assert_eq!(heat_color(1.0), "#ff0000");
+        assert_eq!(heat_color(0.5), "#00dc00");
+        assert_eq!(heat_color(2.0), "#ff0000");
+    }
+}
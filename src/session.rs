@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+
+use crate::{insert_pt, move_pt, remove_pt, FSPoint, FScript, FunscriptError};
+
+/// number of edits an [`EditSession`] remembers by default
+pub const DEFAULT_HISTORY: usize = 100;
+
+/// the actions in `index..index + removed.len()` were replaced by `inserted`
+#[derive(Debug)]
+struct Change {
+    index: usize,
+    removed: Vec<FSPoint>,
+    inserted: Vec<FSPoint>,
+}
+
+impl Change {
+    /// the smallest change that turns `old` into `new`
+    fn between(old: &[FSPoint], new: &[FSPoint]) -> Change {
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Change {
+            index: prefix,
+            removed: old[prefix..old.len() - suffix].to_vec(),
+            inserted: new[prefix..new.len() - suffix].to_vec(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Edit {
+    change: Change,
+    /// the rest of the script before and after, for transforms that touch more than
+    /// the actions, stored with empty action lists
+    other: Option<Box<(FScript, FScript)>>,
+}
+
+/// an [`FScript`] with undo and redo of the edits made through it
+/// point edits only remember the points they touched, transforms remember the
+/// actions that changed and the rest of the script only if it changed too
+#[derive(Debug)]
+pub struct EditSession {
+    script: FScript,
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    max_history: usize,
+}
+
+impl EditSession {
+    pub fn new(script: FScript) -> Self {
+        Self::with_history(script, DEFAULT_HISTORY)
+    }
+
+    /// a session that forgets the oldest edits beyond `max_history`
+    pub fn with_history(script: FScript, max_history: usize) -> Self {
+        Self {
+            script,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            max_history,
+        }
+    }
+
+    pub fn script(&self) -> &FScript {
+        &self.script
+    }
+
+    /// ends the session, returning the edited script
+    pub fn into_script(self) -> FScript {
+        self.script
+    }
+
+    /// adds a point, see [`insert_pt`]
+    pub fn insert(&mut self, at: i32, pos: i32) -> usize {
+        let actions = &self.script.actions;
+        let replaced = actions
+            .get(actions.partition_point(|pt| pt.at < at))
+            .filter(|pt| pt.at == at)
+            .cloned();
+        let index = insert_pt(&mut self.script, at, pos);
+        self.push(Edit {
+            change: Change {
+                index,
+                removed: replaced.into_iter().collect(),
+                inserted: vec![FSPoint { at, pos }],
+            },
+            other: None,
+        });
+        index
+    }
+
+    /// removes the point at `idx`, see [`remove_pt`]
+    pub fn remove(&mut self, idx: usize) -> Result<FSPoint, FunscriptError> {
+        let pt = remove_pt(&mut self.script, idx)?;
+        self.push(Edit {
+            change: Change {
+                index: idx,
+                removed: vec![pt.clone()],
+                inserted: Vec::new(),
+            },
+            other: None,
+        });
+        Ok(pt)
+    }
+
+    /// removes every point with `start_ms <= at <= end_ms`, returns how many were removed
+    pub fn remove_range(&mut self, start_ms: i64, end_ms: i64) -> usize {
+        let actions = &mut self.script.actions;
+        let start = actions.partition_point(|pt| (pt.at as i64) < start_ms);
+        let end = actions.partition_point(|pt| (pt.at as i64) <= end_ms);
+        let removed: Vec<FSPoint> = actions.drain(start..end.max(start)).collect();
+        let count = removed.len();
+        if count > 0 {
+            self.push(Edit {
+                change: Change {
+                    index: start,
+                    removed,
+                    inserted: Vec::new(),
+                },
+                other: None,
+            });
+        }
+        count
+    }
+
+    /// moves the point at `idx`, see [`move_pt`]
+    pub fn move_pt(&mut self, idx: usize, at: i32, pos: i32) -> Result<(), FunscriptError> {
+        let old = self.script.actions.get(idx).cloned();
+        move_pt(&mut self.script, idx, at, pos)?;
+        self.push(Edit {
+            change: Change {
+                index: idx,
+                removed: old.into_iter().collect(),
+                inserted: vec![FSPoint { at, pos }],
+            },
+            other: None,
+        });
+        Ok(())
+    }
+
+    /// runs any edit on the script as one undoable step
+    /// e.g. `session.transform(transform::invert)`
+    pub fn transform<R>(&mut self, edit: impl FnOnce(&mut FScript) -> R) -> R {
+        let old_actions = self.script.actions.clone();
+        let old_other = without_actions(&mut self.script);
+        let result = edit(&mut self.script);
+
+        let change = Change::between(&old_actions, &self.script.actions);
+        let new_other = without_actions(&mut self.script);
+        let other = (old_other != new_other).then(|| Box::new((old_other, new_other)));
+        if !change.removed.is_empty() || !change.inserted.is_empty() || other.is_some() {
+            self.push(Edit { change, other });
+        }
+        result
+    }
+
+    /// reverts the last edit, false if there was nothing to undo
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.undo.pop_back() else {
+            return false;
+        };
+        let change = &edit.change;
+        let end = change.index + change.inserted.len();
+        self.script
+            .actions
+            .splice(change.index..end, change.removed.iter().cloned());
+        if let Some(other) = &edit.other {
+            restore(&mut self.script, &other.0);
+        }
+        self.redo.push(edit);
+        true
+    }
+
+    /// applies the last undone edit again, false if there was nothing to redo
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        let change = &edit.change;
+        let end = change.index + change.removed.len();
+        self.script
+            .actions
+            .splice(change.index..end, change.inserted.iter().cloned());
+        if let Some(other) = &edit.other {
+            restore(&mut self.script, &other.1);
+        }
+        self.undo.push_back(edit);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// forgets every edit, e.g. after saving
+    pub fn clear_history(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    fn push(&mut self, edit: Edit) {
+        self.redo.clear();
+        self.undo.push_back(edit);
+        while self.undo.len() > self.max_history {
+            self.undo.pop_front();
+        }
+    }
+}
+
+/// a copy of everything but the actions
+fn without_actions(script: &mut FScript) -> FScript {
+    let actions = std::mem::take(&mut script.actions);
+    let copy = script.clone();
+    script.actions = actions;
+    copy
+}
+
+/// puts back everything but the actions
+fn restore(script: &mut FScript, other: &FScript) {
+    let actions = std::mem::take(&mut script.actions);
+    *script = other.clone();
+    script.actions = actions;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_funscript, transform};
+
+    fn times(session: &EditSession) -> Vec<(i32, i32)> {
+        session
+            .script()
+            .actions
+            .iter()
+            .map(|pt| (pt.at, pt.pos))
+            .collect()
+    }
+
+    #[test]
+    fn test_undo_redo_point_edits() {
+        let mut session = EditSession::new(FScript::default());
+        session.insert(0, 0);
+        session.insert(200, 100);
+        session.insert(100, 50);
+        session.insert(100, 60);
+        session.move_pt(2, 300, 90).unwrap();
+        session.remove(0).unwrap();
+        assert_eq!(times(&session), vec![(100, 60), (300, 90)]);
+        assert!(session.move_pt(0, 300, 0).is_err());
+
+        assert!(session.undo());
+        assert!(session.undo());
+        assert_eq!(times(&session), vec![(0, 0), (100, 60), (200, 100)]);
+        assert!(session.undo());
+        assert_eq!(times(&session), vec![(0, 0), (100, 50), (200, 100)]);
+        assert!(session.redo());
+        assert!(session.redo());
+        assert_eq!(times(&session), vec![(0, 0), (100, 60), (300, 90)]);
+
+        // a new edit drops the redo history
+        assert_eq!(session.remove_range(0, 100), 2);
+        assert!(!session.redo());
+        while session.undo() {}
+        assert!(session.script().actions.is_empty());
+    }
+
+    #[test]
+    fn test_undo_insert_over_duplicate_times() {
+        let mut script = FScript::default();
+        for (at, pos) in [(0, 0), (100, 10), (100, 20), (200, 30)] {
+            script.actions.push(FSPoint { at, pos });
+        }
+        let mut session = EditSession::new(script);
+        assert_eq!(session.insert(100, 50), 1);
+        assert_eq!(
+            times(&session),
+            vec![(0, 0), (100, 50), (100, 20), (200, 30)]
+        );
+        assert!(session.undo());
+        assert_eq!(
+            times(&session),
+            vec![(0, 0), (100, 10), (100, 20), (200, 30)]
+        );
+    }
+
+    #[test]
+    fn test_undo_transform_and_history_limit() {
+        let original = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        let mut session = EditSession::with_history(original.clone(), 2);
+        session.transform(|s| transform::shift(s, 1000, transform::NegativeTimes::Clamp));
+        session.transform(transform::invert);
+        session.insert(5, 5);
+        assert!(session.undo());
+        assert!(session.undo());
+        assert!(!session.undo() && session.can_redo());
+        assert_ne!(session.script(), &original);
+        assert_eq!(
+            session.script().actions[0].at,
+            original.actions[0].at + 1000
+        );
+
+        let mut session = EditSession::new(original.clone());
+        session.transform(|s| transform::shift(s, 1000, transform::NegativeTimes::Clamp));
+        session.undo();
+        assert_eq!(session.into_script(), original);
+    }
+}
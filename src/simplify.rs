@@ -0,0 +1,439 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use mint::Point2;
+use ramer_douglas_peucker::rdp;
+
+use crate::{apply_rdp, interpolate, FSPoint, FScript};
+
+/// a point reduction algorithm for [`simplify`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    /// ramer-douglas-peucker, see [`apply_rdp`]
+    Rdp { epsilon: f64 },
+    /// ramer-douglas-peucker between pinned peaks, see [`simplify_preserving_peaks`]
+    PeakPreserving { epsilon: f64 },
+    /// visvalingam-whyatt, see [`visvalingam_whyatt`]
+    VisvalingamWhyatt { min_area: f64 },
+}
+
+/// reduces the actions with the given algorithm
+pub fn simplify(script: &mut FScript, algorithm: Algorithm) {
+    match algorithm {
+        Algorithm::Rdp { epsilon } => apply_rdp(script, epsilon),
+        Algorithm::PeakPreserving { epsilon } => simplify_preserving_peaks(script, epsilon),
+        Algorithm::VisvalingamWhyatt { min_area } => {
+            let keep = visvalingam_whyatt(&script.actions, min_area);
+            retain_indices(&mut script.actions, keep);
+        }
+    }
+}
+
+/// how far a simplified curve strays from the original
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ErrorStats {
+    pub max_error: f64,
+    pub mean_error: f64,
+    /// root mean square, weighs large errors more than the mean does
+    pub rms_error: f64,
+}
+
+/// position error of `simplified` measured at every original point
+/// useful to compare algorithms and settings on the same script
+pub fn simplification_error(original: &[FSPoint], simplified: &[FSPoint]) -> ErrorStats {
+    let mut stats = ErrorStats::default();
+    if original.is_empty() {
+        return stats;
+    }
+    let (mut sum, mut squares) = (0.0, 0.0);
+    for pt in original {
+        let pos = interpolate(simplified, pt.at as i64).unwrap_or(0.0);
+        let error = (pt.pos as f64 - pos).abs();
+        stats.max_error = stats.max_error.max(error);
+        sum += error;
+        squares += error * error;
+    }
+    stats.mean_error = sum / original.len() as f64;
+    stats.rms_error = (squares / original.len() as f64).sqrt();
+    stats
+}
+
+/// ramer-douglas-peucker straight over the point buffer, without copying the points
+/// meant for huge recordings, e.g. `rdp_in_place(&mut script.raw_actions, 1.0)`;
+/// a point is dropped when it lies within `epsilon` of the line between the kept
+/// points around it, with time in ms and position in units
+pub fn rdp_in_place(points: &mut Vec<FSPoint>, epsilon: f64) {
+    let n = points.len();
+    if n < 3 {
+        return;
+    }
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[n - 1] = true;
+    let mut stack = vec![(0, n - 1)];
+    while let Some((first, last)) = stack.pop() {
+        if last - first < 2 {
+            continue;
+        }
+        let (a, b) = (&points[first], &points[last]);
+        let (dx, dy) = ((b.at - a.at) as f64, (b.pos - a.pos) as f64);
+        let length = dx.hypot(dy);
+
+        // compare the unscaled cross products, dividing once at the end
+        let (mut max_distance, mut max_idx) = (0.0, first);
+        for (i, pt) in points[first + 1..last].iter().enumerate() {
+            let (px, py) = ((pt.at - a.at) as f64, (pt.pos - a.pos) as f64);
+            let distance = if length == 0.0 {
+                px * px + py * py
+            } else {
+                (dx * py - dy * px).abs()
+            };
+            if distance > max_distance {
+                (max_distance, max_idx) = (distance, first + 1 + i);
+            }
+        }
+        let max_distance = if length == 0.0 {
+            max_distance.sqrt()
+        } else {
+            max_distance / length
+        };
+        if max_distance > epsilon {
+            keep[max_idx] = true;
+            stack.push((first, max_idx));
+            stack.push((max_idx, last));
+        }
+    }
+
+    let mut idx = 0;
+    points.retain(|_| {
+        idx += 1;
+        keep[idx - 1]
+    });
+}
+
+/// summary of a simplification for showing to users
+/// displays as `reduced 8000 -> 1200 points, max error 3.0 units`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SimplificationReport {
+    pub original_points: usize,
+    pub simplified_points: usize,
+    pub removed: usize,
+    /// largest position difference, in units
+    pub max_deviation: f64,
+    pub rms_error: f64,
+}
+
+impl SimplificationReport {
+    /// compares the actions before and after any simplification
+    pub fn new(original: &[FSPoint], simplified: &[FSPoint]) -> Self {
+        let error = simplification_error(original, simplified);
+        Self {
+            original_points: original.len(),
+            simplified_points: simplified.len(),
+            removed: original.len().saturating_sub(simplified.len()),
+            max_deviation: error.max_error,
+            rms_error: error.rms_error,
+        }
+    }
+}
+
+impl std::fmt::Display for SimplificationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "reduced {} -> {} points, max error {:.1} units",
+            self.original_points, self.simplified_points, self.max_deviation
+        )
+    }
+}
+
+/// indices of the points kept by visvalingam-whyatt
+/// the point forming the smallest triangle with its neighbours is removed until every
+/// remaining triangle is at least `min_area`, with time in ms and position in units
+/// removing by area tends to keep the feel of a stroke better than distance based rdp
+pub fn visvalingam_whyatt(points: &[FSPoint], min_area: f64) -> Vec<usize> {
+    let n = points.len();
+    if n < 3 {
+        return (0..n).collect();
+    }
+    let area = |a: usize, b: usize, c: usize| {
+        let (p, q, r) = (&points[a], &points[b], &points[c]);
+        let cross = (q.at - p.at) as f64 * (r.pos - p.pos) as f64
+            - (r.at - p.at) as f64 * (q.pos - p.pos) as f64;
+        cross.abs() / 2.0
+    };
+
+    let mut prev: Vec<usize> = (0..n).map(|i| i.wrapping_sub(1)).collect();
+    let mut next: Vec<usize> = (1..=n).collect();
+    let mut removed = vec![false; n];
+    // areas are stored as ordered bits, positive floats sort the same way
+    let mut heap = BinaryHeap::new();
+    let mut current: Vec<f64> = (0..n)
+        .map(|i| match i {
+            // the ends are never removed
+            0 => f64::INFINITY,
+            i if i == n - 1 => f64::INFINITY,
+            i => area(i - 1, i, i + 1),
+        })
+        .collect();
+    for (i, value) in current.iter().enumerate().take(n - 1).skip(1) {
+        heap.push(Reverse((value.to_bits(), i)));
+    }
+
+    while let Some(Reverse((bits, i))) = heap.pop() {
+        let value = f64::from_bits(bits);
+        if value >= min_area {
+            break;
+        }
+        // skip stale entries left over from before a neighbour was removed
+        if removed[i] || bits != current[i].to_bits() {
+            continue;
+        }
+        removed[i] = true;
+        let (p, q) = (prev[i], next[i]);
+        next[p] = q;
+        prev[q] = p;
+        for j in [p, q] {
+            if j != 0 && j != n - 1 {
+                // a neighbour's area never drops below the removed one's
+                current[j] = area(prev[j], j, next[j]).max(value);
+                heap.push(Reverse((current[j].to_bits(), j)));
+            }
+        }
+    }
+    (0..n).filter(|&i| !removed[i]).collect()
+}
+
+/// simplifies the actions with ramer-douglas-peucker using the smallest epsilon
+/// that leaves at most `target_points`, returns the epsilon that was used
+/// the first and last action are always kept, so fewer than 2 points can't be reached
+pub fn simplify_to_count(script: &mut FScript, target_points: usize) -> f64 {
+    if script.actions.len() <= target_points {
+        return 0.0;
+    }
+    let points: Vec<Point2<i32>> = script
+        .actions
+        .iter()
+        .map(|pt| Point2 {
+            x: pt.at,
+            y: pt.pos,
+        })
+        .collect();
+
+    // grow the upper bound until it's enough, then bisect
+    let (mut lo, mut hi) = (0.0, 1.0);
+    let mut best = rdp(&points, hi);
+    while best.len() > target_points && hi < 1e9 {
+        lo = hi;
+        hi *= 2.0;
+        best = rdp(&points, hi);
+    }
+    for _ in 0..32 {
+        let mid = (lo + hi) / 2.0;
+        let kept = rdp(&points, mid);
+        if kept.len() <= target_points {
+            hi = mid;
+            best = kept;
+        } else {
+            lo = mid;
+        }
+    }
+
+    retain_indices(&mut script.actions, best);
+    hi
+}
+
+/// ramer-douglas-peucker that never removes a peak or valley
+/// every direction reversal is kept and only the points between them are simplified
+pub fn simplify_preserving_peaks(script: &mut FScript, epsilon: f64) {
+    let actions = &script.actions;
+    if actions.len() < 3 {
+        return;
+    }
+    let mut pinned = vec![0];
+    let mut direction = 0;
+    for i in 1..actions.len() {
+        let delta = (actions[i].pos - actions[i - 1].pos).signum();
+        if delta != 0 && direction != 0 && delta != direction {
+            pinned.push(i - 1);
+        }
+        if delta != 0 {
+            direction = delta;
+        }
+    }
+    pinned.push(actions.len() - 1);
+    pinned.dedup();
+
+    let mut keep = Vec::with_capacity(pinned.len());
+    for pair in pinned.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let span: Vec<Point2<i32>> = actions[start..=end]
+            .iter()
+            .map(|pt| Point2 {
+                x: pt.at,
+                y: pt.pos,
+            })
+            .collect();
+        keep.extend(rdp(&span, epsilon).into_iter().map(|i| start + i));
+    }
+    retain_indices(&mut script.actions, keep);
+}
+
+/// keeps only the points at the given indices
+fn retain_indices<T>(points: &mut Vec<T>, mut indices: Vec<usize>) {
+    indices.sort_unstable();
+    indices.dedup();
+    let mut keep = indices.into_iter().peekable();
+    let mut idx = 0;
+    points.retain(|_| {
+        let kept = keep.next_if_eq(&idx).is_some();
+        idx += 1;
+        kept
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{apply_rdp, load_funscript, FSPoint};
+
+    #[test]
+    fn test_simplify_to_count() {
+        let mut s = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        let (first, last) = (s.actions[0].at, s.actions.last().unwrap().at);
+        let epsilon = simplify_to_count(&mut s, 1000);
+        assert!(s.actions.len() <= 1000);
+        assert!(s.actions.len() > 900, "only {} left", s.actions.len());
+        assert!(epsilon > 0.0);
+        assert_eq!(s.actions[0].at, first);
+        assert_eq!(s.actions.last().unwrap().at, last);
+
+        let before = s.actions.len();
+        assert_eq!(simplify_to_count(&mut s, 5000), 0.0);
+        assert_eq!(s.actions.len(), before);
+    }
+
+    /// a small bump near the top that plain rdp flattens away with a large epsilon
+    fn bumpy() -> FScript {
+        let mut s = FScript::default();
+        for (at, pos) in [
+            (0, 0),
+            (100, 50),
+            (200, 100),
+            (300, 96),
+            (400, 100),
+            (1000, 0),
+        ] {
+            s.actions.push(FSPoint { at, pos });
+        }
+        s
+    }
+
+    fn times(s: &FScript) -> Vec<i32> {
+        s.actions.iter().map(|pt| pt.at).collect()
+    }
+
+    #[test]
+    fn test_visvalingam_whyatt() {
+        let mut s = bumpy();
+        // the bump at 300 makes a 400 ms*unit triangle, 100 sits on a straight line
+        simplify(&mut s, Algorithm::VisvalingamWhyatt { min_area: 300.0 });
+        assert_eq!(times(&s), vec![0, 200, 300, 400, 1000]);
+        let error = simplification_error(&bumpy().actions, &s.actions);
+        assert_eq!(error, ErrorStats::default());
+
+        simplify(&mut s, Algorithm::VisvalingamWhyatt { min_area: 500.0 });
+        assert_eq!(times(&s), vec![0, 200, 400, 1000]);
+        let error = simplification_error(&bumpy().actions, &s.actions);
+        assert_eq!(error.max_error, 4.0);
+        assert_eq!(error.mean_error, 4.0 / 6.0);
+        assert_eq!(error.rms_error, (16.0f64 / 6.0).sqrt());
+    }
+
+    #[test]
+    fn test_simplification_report() {
+        let original = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        let mut s = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        simplify_to_count(&mut s, 2000);
+
+        let report = SimplificationReport::new(&original.actions, &s.actions);
+        assert_eq!(report.original_points, 3585);
+        assert_eq!(report.removed, 3585 - s.actions.len());
+        assert!(report.max_deviation > 0.0);
+        assert!(report.rms_error > 0.0 && report.rms_error <= report.max_deviation);
+        let text = report.to_string();
+        assert!(text.starts_with(&format!("reduced 3585 -> {} points", s.actions.len())));
+
+        let same = SimplificationReport::new(&original.actions, &original.actions);
+        assert_eq!(same.removed, 0);
+        assert_eq!(same.max_deviation, 0.0);
+    }
+
+    #[test]
+    fn test_rdp_in_place() {
+        let original = load_funscript("./test-scripts/joyfunscripter.funscript").unwrap();
+        let mut raw = original.raw_actions;
+        let before: Vec<(i32, i32)> = raw.iter().map(|pt| (pt.at, pt.pos)).collect();
+        rdp_in_place(&mut raw, 2.0);
+        assert!(raw.len() < before.len());
+        assert_eq!((raw[0].at, raw[0].pos), before[0]);
+
+        // every dropped point is within epsilon of the line it was folded into
+        for &(at, pos) in &before {
+            let idx = raw.partition_point(|pt| pt.at <= at).min(raw.len() - 1);
+            let (a, b) = (&raw[idx.saturating_sub(1)], &raw[idx]);
+            let (dx, dy) = ((b.at - a.at) as f64, (b.pos - a.pos) as f64);
+            let (px, py) = ((at - a.at) as f64, (pos - a.pos) as f64);
+            let distance = match dx.hypot(dy) {
+                0.0 => px.hypot(py),
+                length => (dx * py - dy * px).abs() / length,
+            };
+            assert!(distance <= 2.0 + 1e-9, "point at {at} is {distance} away");
+        }
+    }
+
+    /// `cargo test --release rdp_speed -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn rdp_speed() {
+        let mut points = Vec::new();
+        for i in 0..500_000 {
+            let pos = 50.0 + 50.0 * (i as f64 / 200.0).sin() + (i % 7) as f64 * 0.3;
+            points.push(FSPoint {
+                at: i * 5,
+                pos: pos.round() as i32,
+            });
+        }
+        let copy = || points.clone();
+
+        let mut script = FScript {
+            actions: copy(),
+            ..Default::default()
+        };
+        let start = std::time::Instant::now();
+        apply_rdp(&mut script, 1.0);
+        let copied = start.elapsed();
+
+        let mut buffer = copy();
+        let start = std::time::Instant::now();
+        rdp_in_place(&mut buffer, 1.0);
+        let in_place = start.elapsed();
+        println!(
+            "apply_rdp {copied:?} kept {}, rdp_in_place {in_place:?} kept {}",
+            script.actions.len(),
+            buffer.len()
+        );
+        assert!(in_place < copied);
+    }
+
+    #[test]
+    fn test_simplify_preserving_peaks() {
+        let mut plain = bumpy();
+        apply_rdp(&mut plain, 20.0);
+        assert!(!times(&plain).contains(&300));
+
+        let mut s = bumpy();
+        simplify_preserving_peaks(&mut s, 20.0);
+        assert_eq!(times(&s), vec![0, 200, 300, 400, 1000]);
+    }
+}
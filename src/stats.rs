@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+
+use crate::analysis::{strokes_in, SectionLabel, Stroke, Thresholds};
+use crate::{interpolate, Chapter, FSPoint, FScript};
+
+/// bucket edges for per-script average speed in units per second
+pub const SPEED_BUCKET_EDGES: [f64; 7] = [0.0, 50.0, 100.0, 150.0, 200.0, 300.0, 400.0];
+/// bucket edges for per-script duration in minutes
+pub const DURATION_BUCKET_EDGES: [f64; 7] = [0.0, 1.0, 5.0, 10.0, 20.0, 30.0, 60.0];
+
+/// duration of a script in milliseconds, the time of its last action
+pub fn duration_ms(script: &FScript) -> i64 {
+    script.actions.last().map_or(0, |pt| pt.at as i64)
+}
+
+/// average movement speed over the whole script in position units per second
+pub fn average_speed(script: &FScript) -> f64 {
+    let mut distance = 0.0;
+    let mut time = 0.0;
+    for pair in script.actions.windows(2) {
+        distance += (pair[1].pos - pair[0].pos).abs() as f64;
+        time += (pair[1].at - pair[0].at) as f64;
+    }
+    if time <= 0.0 {
+        return 0.0;
+    }
+    distance / time * 1000.0
+}
+
+/// time spent moving at each intensity, split at the default [`Thresholds`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IntensityBands {
+    pub pause_ms: i64,
+    pub slow_ms: i64,
+    pub medium_ms: i64,
+    pub fast_ms: i64,
+}
+
+/// the numbers a script sharing site shows for one script
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScriptStats {
+    pub duration_ms: i64,
+    pub action_count: usize,
+    /// units per second, see [`average_speed`]
+    pub average_speed: f64,
+    /// median of the per segment speeds
+    pub median_speed: f64,
+    pub max_speed: f64,
+    /// average distance covered by one stroke, a run of moves in the same direction
+    pub average_stroke_length: f64,
+    /// strokes per minute of scripted time, up and down each count as one stroke
+    pub strokes_per_minute: f64,
+    pub bands: IntensityBands,
+}
+
+/// computes the [`ScriptStats`] of a script
+pub fn script_stats(script: &FScript) -> ScriptStats {
+    stats_for(&script.actions, duration_ms(script))
+}
+
+/// computes [`ScriptStats`] for every chapter, in chapter order
+/// the motion is cut at the chapter edges, so a stroke crossing into a chapter counts
+/// with the part that falls inside it
+pub fn stats_per_chapter(script: &FScript) -> Vec<(&Chapter, ScriptStats)> {
+    script
+        .metadata
+        .chapters()
+        .iter()
+        .map(|chapter| {
+            let (start, end) = (chapter.start_time, chapter.end_time.max(chapter.start_time));
+            let mut points = Vec::new();
+            if let Some(pos) = interpolate(&script.actions, start) {
+                points.push(FSPoint {
+                    at: start as i32,
+                    pos: pos.round() as i32,
+                });
+                points.extend_from_slice(script.actions_in_range(start + 1, end - 1));
+                if end > start {
+                    let pos = interpolate(&script.actions, end).unwrap_or(pos);
+                    points.push(FSPoint {
+                        at: end as i32,
+                        pos: pos.round() as i32,
+                    });
+                }
+            }
+            (chapter, stats_for(&points, end - start))
+        })
+        .collect()
+}
+
+/// stats over a run of actions, `duration_ms` is reported as is
+pub(crate) fn stats_for(actions: &[FSPoint], duration_ms: i64) -> ScriptStats {
+    let thresholds = Thresholds::default();
+    let mut stats = ScriptStats {
+        duration_ms,
+        action_count: actions.len(),
+        ..Default::default()
+    };
+
+    let mut speeds = Vec::with_capacity(actions.len());
+    let (mut distance, mut time) = (0.0, 0.0);
+    for pair in actions.windows(2) {
+        let delta = pair[1].pos - pair[0].pos;
+        let ms = (pair[1].at - pair[0].at) as i64;
+        distance += delta.abs() as f64;
+        time += ms as f64;
+        if ms > 0 {
+            let speed = delta.abs() as f64 / ms as f64 * 1000.0;
+            speeds.push(speed);
+            let band = match thresholds.label(speed) {
+                SectionLabel::Pause => &mut stats.bands.pause_ms,
+                SectionLabel::Slow => &mut stats.bands.slow_ms,
+                SectionLabel::Medium => &mut stats.bands.medium_ms,
+                SectionLabel::Fast => &mut stats.bands.fast_ms,
+            };
+            *band += ms;
+        }
+    }
+    let strokes = strokes_in(actions);
+
+    if time > 0.0 {
+        stats.average_speed = distance / time * 1000.0;
+        stats.strokes_per_minute = strokes.len() as f64 / (time / 60_000.0);
+    }
+    stats.max_speed = speeds.iter().copied().fold(0.0, f64::max);
+    stats.median_speed = Summary::from_values(speeds).median;
+    if !strokes.is_empty() {
+        let total: i32 = strokes.iter().map(Stroke::depth).sum();
+        stats.average_stroke_length = total as f64 / strokes.len() as f64;
+    }
+    stats
+}
+
+/// counts of values falling into fixed buckets
+/// bucket `i` covers `edges[i]..edges[i + 1]`, the last bucket is open ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub edges: Vec<f64>,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    fn new(edges: &[f64]) -> Self {
+        Self {
+            edges: edges.to_vec(),
+            counts: vec![0; edges.len()],
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        if let Some(idx) = self.edges.iter().rposition(|&edge| value >= edge) {
+            self.counts[idx] += 1;
+        }
+    }
+}
+
+/// min, max and median of a per-script value
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub min: f64,
+    pub max: f64,
+    pub median: f64,
+}
+
+impl Summary {
+    fn from_values(mut values: Vec<f64>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_by(f64::total_cmp);
+        let mid = values.len() / 2;
+        let median = if values.len().is_multiple_of(2) {
+            (values[mid - 1] + values[mid]) / 2.0
+        } else {
+            values[mid]
+        };
+        Self {
+            min: values[0],
+            max: values[values.len() - 1],
+            median,
+        }
+    }
+}
+
+/// statistics over a whole library of scripts
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionStats {
+    pub script_count: usize,
+    pub total_duration_ms: i64,
+    pub total_actions: usize,
+    /// per-script average speed, see [`SPEED_BUCKET_EDGES`]
+    pub speed_histogram: Histogram,
+    /// per-script duration in minutes, see [`DURATION_BUCKET_EDGES`]
+    pub duration_histogram: Histogram,
+    /// tags sorted by how many scripts use them
+    pub top_tags: Vec<(String, usize)>,
+    /// performers sorted by how many scripts feature them
+    pub top_performers: Vec<(String, usize)>,
+    pub duration: Summary,
+    pub actions: Summary,
+    pub average_speed: Summary,
+}
+
+/// aggregates statistics over a collection of scripts in a single pass
+pub fn aggregate_stats<'a>(scripts: impl IntoIterator<Item = &'a FScript>) -> CollectionStats {
+    let mut speed_histogram = Histogram::new(&SPEED_BUCKET_EDGES);
+    let mut duration_histogram = Histogram::new(&DURATION_BUCKET_EDGES);
+    let mut tags: HashMap<&str, usize> = HashMap::new();
+    let mut performers: HashMap<&str, usize> = HashMap::new();
+    let mut durations = Vec::new();
+    let mut action_counts = Vec::new();
+    let mut speeds = Vec::new();
+    let mut total_duration_ms = 0;
+    let mut total_actions = 0;
+
+    for script in scripts {
+        let duration = duration_ms(script);
+        let speed = average_speed(script);
+        total_duration_ms += duration;
+        total_actions += script.actions.len();
+        speed_histogram.add(speed);
+        duration_histogram.add(duration as f64 / 60_000.0);
+        for tag in &script.metadata.tags {
+            *tags.entry(tag).or_default() += 1;
+        }
+        for performer in &script.metadata.performers {
+            *performers.entry(performer).or_default() += 1;
+        }
+        durations.push(duration as f64);
+        action_counts.push(script.actions.len() as f64);
+        speeds.push(speed);
+    }
+
+    CollectionStats {
+        script_count: durations.len(),
+        total_duration_ms,
+        total_actions,
+        speed_histogram,
+        duration_histogram,
+        top_tags: ranked(tags),
+        top_performers: ranked(performers),
+        duration: Summary::from_values(durations),
+        actions: Summary::from_values(action_counts),
+        average_speed: Summary::from_values(speeds),
+    }
+}
+
+/// sorts counts descending, ties broken by name
+fn ranked(counts: HashMap<&str, usize>) -> Vec<(String, usize)> {
+    let mut ranked: Vec<_> = counts
+        .into_iter()
+        .map(|(name, count)| (name.to_string(), count))
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_funscript, FSPoint};
+
+    #[test]
+    fn test_script_stats() {
+        let mut s = FScript::default();
+        // two full strokes per second for 3 seconds, then a 2 second pause
+        for i in 0..=6 {
+            s.actions.push(FSPoint {
+                at: i * 500,
+                pos: if i % 2 == 0 { 0 } else { 100 },
+            });
+        }
+        s.actions.push(FSPoint { at: 5000, pos: 0 });
+
+        let stats = script_stats(&s);
+        assert_eq!(stats.duration_ms, 5000);
+        assert_eq!(stats.action_count, 8);
+        assert_eq!(stats.max_speed, 200.0);
+        assert_eq!(stats.median_speed, 200.0);
+        assert_eq!(stats.average_speed, 120.0);
+        assert_eq!(stats.average_stroke_length, 100.0);
+        assert_eq!(stats.strokes_per_minute, 6.0 / (5.0 / 60.0));
+        assert_eq!(
+            stats.bands,
+            IntensityBands {
+                pause_ms: 2000,
+                slow_ms: 0,
+                medium_ms: 3000,
+                fast_ms: 0,
+            }
+        );
+        assert_eq!(script_stats(&FScript::default()), ScriptStats::default());
+    }
+
+    #[test]
+    fn test_stats_per_chapter() {
+        let mut s = FScript::default();
+        for i in 0..=10 {
+            s.actions.push(FSPoint {
+                at: i * 1000,
+                pos: if i % 2 == 0 { 0 } else { 100 },
+            });
+        }
+        for (name, start, end) in [("first", 0, 4000), ("rest", 4500, 10000)] {
+            s.metadata.chapters_mut().push(Chapter {
+                name: name.to_string(),
+                start_time: start,
+                end_time: end,
+            });
+        }
+
+        let per_chapter = stats_per_chapter(&s);
+        assert_eq!(per_chapter.len(), 2);
+        let (chapter, first) = &per_chapter[0];
+        assert_eq!(chapter.name, "first");
+        assert_eq!(first.duration_ms, 4000);
+        assert_eq!(first.action_count, 5);
+        assert_eq!(first.average_speed, 100.0);
+        // starts halfway through a stroke
+        let rest = &per_chapter[1].1;
+        assert_eq!(rest.action_count, 7);
+        assert_eq!(rest.average_stroke_length, (50.0 + 5.0 * 100.0) / 6.0);
+    }
+
+    #[test]
+    fn test_aggregate_stats() {
+        let mut jfs = load_funscript("./test-scripts/joyfunscripter.funscript").unwrap();
+        let mut ofs = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        jfs.metadata.tags = vec!["vr".to_string(), "pov".to_string()];
+        ofs.metadata.tags = vec!["vr".to_string()];
+        ofs.metadata.performers = vec!["someone".to_string()];
+
+        let mut synthetic = FScript::default();
+        synthetic.metadata.tags = vec!["vr".to_string(), "pov".to_string(), "2d".to_string()];
+        for i in 0..11 {
+            synthetic.actions.push(FSPoint {
+                at: i * 1000,
+                pos: if i % 2 == 0 { 0 } else { 100 },
+            });
+        }
+
+        let scripts = [jfs, ofs, synthetic];
+        let stats = aggregate_stats(&scripts);
+        assert_eq!(stats.script_count, 3);
+        assert_eq!(stats.total_actions, 2509 + 3585 + 11);
+        assert_eq!(
+            stats.total_duration_ms,
+            scripts.iter().map(duration_ms).sum::<i64>()
+        );
+        assert_eq!(
+            stats.top_tags,
+            vec![
+                ("vr".to_string(), 3),
+                ("pov".to_string(), 2),
+                ("2d".to_string(), 1)
+            ]
+        );
+        assert_eq!(stats.top_performers, vec![("someone".to_string(), 1)]);
+        assert_eq!(stats.speed_histogram.counts.iter().sum::<usize>(), 3);
+        assert_eq!(stats.actions.min, 11.0);
+        assert_eq!(stats.actions.median, 2509.0);
+        assert_eq!(stats.actions.max, 3585.0);
+        // the synthetic script moves 100 units every second
+        assert_eq!(average_speed(&scripts[2]), 100.0);
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt::Write;
+
+use crate::analysis::{heatmap, speed_between, Thresholds};
+use crate::FScript;
+
+/// number of glyphs in a full intensity bar
+const BAR_WIDTH: usize = 10;
+
+/// subtitle cues for every `bucket_ms` of the script in SubRip format
+/// each cue shows an intensity bar, the speed label and the average speed
+pub fn export_srt(script: &FScript, bucket_ms: i64) -> String {
+    let mut out = String::new();
+    for (i, (start, end, text)) in cues(script, bucket_ms).into_iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "{}\n{} --> {}\n{text}\n",
+            i + 1,
+            timestamp(start, ','),
+            timestamp(end, ',')
+        );
+    }
+    out
+}
+
+/// the same cues as [`export_srt`] in WebVTT format
+pub fn export_vtt(script: &FScript, bucket_ms: i64) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for (start, end, text) in cues(script, bucket_ms) {
+        let _ = writeln!(
+            out,
+            "{} --> {}\n{text}\n",
+            timestamp(start, '.'),
+            timestamp(end, '.')
+        );
+    }
+    out
+}
+
+/// start, end and text of every cue
+fn cues(script: &FScript, bucket_ms: i64) -> Vec<(i64, i64, String)> {
+    let bucket_ms = bucket_ms.max(1);
+    let thresholds = Thresholds::default();
+    heatmap(script, bucket_ms)
+        .into_iter()
+        .enumerate()
+        .map(|(i, level)| {
+            let start = i as i64 * bucket_ms;
+            let end = start + bucket_ms;
+            let speed = speed_between(&script.actions, start, end);
+            let filled = (level * BAR_WIDTH as f32).round() as usize;
+            let bar = "\u{2588}".repeat(filled) + &"\u{2591}".repeat(BAR_WIDTH - filled);
+            let label = thresholds.label(speed).name();
+            (start, end, format!("{bar} {label} {speed:.0}/s"))
+        })
+        .collect()
+}
+
+/// `hh:mm:ss,mmm` with the given millisecond separator
+fn timestamp(ms: i64, separator: char) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FSPoint;
+
+    fn script() -> FScript {
+        let mut s = FScript::default();
+        // 400 units/s for 2s, then a 2s pause
+        for i in 0..=8 {
+            let pos = if i % 2 == 0 { 0 } else { 100 };
+            s.actions.push(FSPoint { at: i * 250, pos });
+        }
+        s.actions.push(FSPoint { at: 4000, pos: 0 });
+        s
+    }
+
+    #[test]
+    fn test_export_srt() {
+        let srt = export_srt(&script(), 2000);
+        let expected = "1\n00:00:00,000 --> 00:00:02,000\n\u{2588}\u{2588}\u{2588}\u{2588}\u{2588}\u{2588}\u{2588}\u{2588}\u{2588}\u{2588} fast 400/s\n\n\
+                        2\n00:00:02,000 --> 00:00:04,000\n\u{2591}\u{2591}\u{2591}\u{2591}\u{2591}\u{2591}\u{2591}\u{2591}\u{2591}\u{2591} pause 0/s\n\n";
+        assert_eq!(srt, expected);
+        assert_eq!(timestamp(3_723_004, ','), "01:02:03,004");
+    }
+
+    #[test]
+    fn test_export_vtt() {
+        let vtt = export_vtt(&script(), 1000);
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:01.000\n"));
+        assert_eq!(vtt.matches(" --> ").count(), 4);
+        assert!(export_vtt(&FScript::default(), 1000) == "WEBVTT\n\n");
+    }
+}
@@ -0,0 +1,358 @@
+use std::time::Duration;
+
+use crate::{FSPoint, FScript};
+
+/// result of [`estimate_offset`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OffsetEstimate {
+    /// how many ms later `b` runs than `a`
+    /// shifting `a` by this amount lines it up with `b`
+    pub offset_ms: i64,
+    /// pearson correlation at the chosen offset, -1.0 to 1.0
+    pub score: f64,
+    /// false when the match is weak or sits on the edge of the search window
+    pub confident: bool,
+}
+
+/// estimates the time offset between two scripts of the same scene
+/// both are resampled every `step_ms` and the lag in `-max_offset_ms..=max_offset_ms`
+/// with the highest correlation wins
+pub fn estimate_offset(
+    a: &FScript,
+    b: &FScript,
+    max_offset_ms: i64,
+    step_ms: i64,
+) -> OffsetEstimate {
+    let none = OffsetEstimate {
+        offset_ms: 0,
+        score: 0.0,
+        confident: false,
+    };
+    let (Some(first), Some(last)) = (a.actions.first(), a.actions.last()) else {
+        return none;
+    };
+    if b.actions.is_empty() || step_ms <= 0 {
+        return none;
+    }
+
+    let max_lag = max_offset_ms.max(0) / step_ms;
+    let start = first.at as i64;
+    let count = ((last.at as i64 - start) / step_ms + 1) as usize;
+    let a_grid = sample(a, start, step_ms, count);
+    let b_grid = sample(
+        b,
+        start - max_lag * step_ms,
+        step_ms,
+        count + 2 * max_lag as usize,
+    );
+
+    let mut best = none;
+    best.score = f64::NEG_INFINITY;
+    for lag in -max_lag..=max_lag {
+        let window = &b_grid[(lag + max_lag) as usize..][..count];
+        let score = correlation(&a_grid, window);
+        if score > best.score {
+            best.offset_ms = lag * step_ms;
+            best.score = score;
+        }
+    }
+    best.confident = best.score >= 0.5 && best.offset_ms.abs() < max_lag * step_ms;
+    best
+}
+
+/// resolution scripts are resampled at by [`similarity`]
+pub const SIMILARITY_STEP_MS: i64 = 50;
+
+/// how alike two scripts are, from 0.0 for unrelated to 1.0 for identical
+/// the position correlation over the time both cover, scaled by how much of their
+/// combined length that is, so a short excerpt of a long script scores low
+pub fn similarity(a: &FScript, b: &FScript) -> f32 {
+    let (Some((a_first, a_last)), Some((b_first, b_last))) = (span(a), span(b)) else {
+        return 0.0;
+    };
+    let (start, end) = (a_first.max(b_first), a_last.min(b_last));
+    if end <= start {
+        return 0.0;
+    }
+    let count = ((end - start) / SIMILARITY_STEP_MS + 1) as usize;
+    let a_grid = sample(a, start, SIMILARITY_STEP_MS, count);
+    let b_grid = sample(b, start, SIMILARITY_STEP_MS, count);
+    let coverage = (end - start) as f64 / (a_last.max(b_last) - a_first.min(b_first)) as f64;
+    (correlation(&a_grid, &b_grid).max(0.0) * coverage) as f32
+}
+
+/// like [`similarity`] but tolerant of local timing drift
+/// uses dynamic time warping that lets either script run up to `band_ms` ahead,
+/// 1.0 means the curves match exactly after warping, 0.0 that they are a full
+/// stroke apart on average
+pub fn dtw_similarity(a: &FScript, b: &FScript, step_ms: i64, band_ms: i64) -> f32 {
+    let (Some((a_first, a_last)), Some((b_first, b_last))) = (span(a), span(b)) else {
+        return 0.0;
+    };
+    let step = step_ms.max(1);
+    let start = a_first.min(b_first);
+    let count = ((a_last.max(b_last) - start) / step + 1) as usize;
+    let a_grid = sample(a, start, step, count);
+    let b_grid = sample(b, start, step, count);
+    let band = (band_ms.max(0) / step) as usize;
+
+    // rolling rows of the cost matrix, cells outside the band stay infinite
+    let mut prev = vec![f64::INFINITY; count + 1];
+    let mut cur = vec![f64::INFINITY; count + 1];
+    prev[0] = 0.0;
+    for i in 1..=count {
+        cur.fill(f64::INFINITY);
+        let lo = i.saturating_sub(band).max(1);
+        let hi = (i + band).min(count);
+        for j in lo..=hi {
+            let cost = (a_grid[i - 1] - b_grid[j - 1]).abs();
+            cur[j] = cost + prev[j - 1].min(prev[j]).min(cur[j - 1]);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    let mean = prev[count] / count as f64;
+    (1.0 - mean / 100.0).clamp(0.0, 1.0) as f32
+}
+
+/// time of the first and last action
+fn span(script: &FScript) -> Option<(i64, i64)> {
+    let first = script.actions.first()?.at as i64;
+    let last = script.actions.last()?.at as i64;
+    Some((first, last))
+}
+
+/// finds the shift in `-max_shift_ms..=max_shift_ms` that best lines `a` up with `b`
+/// e.g. a script and a re-cut of the same video, or a script and a reference script
+/// searches coarsely first and then refines around the best match to 10ms
+pub fn find_offset(a: &FScript, b: &FScript, max_shift_ms: i64) -> OffsetEstimate {
+    const COARSE_STEP_MS: i64 = 100;
+    const FINE_STEP_MS: i64 = 10;
+    let coarse = estimate_offset(a, b, max_shift_ms, COARSE_STEP_MS);
+    if coarse.score <= 0.0 {
+        return coarse;
+    }
+
+    let shifted = FScript {
+        actions: a
+            .actions
+            .iter()
+            .map(|pt| FSPoint {
+                at: (pt.at as i64 + coarse.offset_ms) as i32,
+                pos: pt.pos,
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let fine = estimate_offset(&shifted, b, COARSE_STEP_MS, FINE_STEP_MS);
+    let offset_ms = (coarse.offset_ms + fine.offset_ms).clamp(-max_shift_ms, max_shift_ms);
+    OffsetEstimate {
+        offset_ms,
+        score: fine.score.max(coarse.score),
+        confident: fine.score >= 0.5 && offset_ms.abs() < max_shift_ms,
+    }
+}
+
+/// result of [`check_sync`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncReport {
+    pub video_ms: i64,
+    /// time of the last action
+    pub last_action_ms: i64,
+    /// the metadata duration in ms, `None` if the script has none
+    pub metadata_ms: Option<i64>,
+    /// how far the last action runs past the end of the video, 0 if it doesn't
+    pub overrun_ms: i64,
+    /// metadata duration minus video duration, `None` without a metadata duration
+    pub metadata_drift_ms: Option<i64>,
+    /// true when both the overrun and the metadata drift are within the tolerance
+    pub in_sync: bool,
+    /// the time scale that would fit the script to the video, `None` when in sync
+    pub suggested_scale: Option<f64>,
+}
+
+impl SyncReport {
+    /// applies [`SyncReport::suggested_scale`] to the script, false if there was none
+    pub fn fix(&self, script: &mut FScript) -> bool {
+        let Some(factor) = self.suggested_scale else {
+            return false;
+        };
+        crate::transform::time_scale(script, factor);
+        true
+    }
+}
+
+/// compares the length of a script against the length of its video
+/// a script made for a different cut or frame rate runs past the end of the video or
+/// records a different duration, `tolerance_ms` is the drift that is still accepted
+/// the metadata duration is in whole seconds so it is always allowed another second
+pub fn check_sync(script: &FScript, video_duration: Duration, tolerance_ms: i64) -> SyncReport {
+    let video_ms = video_duration.as_millis().min(i64::MAX as u128) as i64;
+    let last_action_ms = script.actions.last().map_or(0, |pt| pt.at as i64);
+    let metadata_ms =
+        (script.metadata.duration > 0).then(|| script.metadata.duration as i64 * 1000);
+    let overrun_ms = (last_action_ms - video_ms).max(0);
+    let metadata_drift_ms = metadata_ms.map(|ms| ms - video_ms);
+
+    let tolerance = tolerance_ms.max(0);
+    let in_sync = overrun_ms <= tolerance
+        && metadata_drift_ms.is_none_or(|drift| drift.abs() <= tolerance + 1000);
+    let suggested_scale = if in_sync || video_ms <= 0 {
+        None
+    } else {
+        // a script with a duration was timed against a video of that length
+        let timed_for = metadata_ms.unwrap_or(last_action_ms);
+        (timed_for > 0).then(|| video_ms as f64 / timed_for as f64)
+    };
+    SyncReport {
+        video_ms,
+        last_action_ms,
+        metadata_ms,
+        overrun_ms,
+        metadata_drift_ms,
+        in_sync,
+        suggested_scale,
+    }
+}
+
+/// linearly interpolated positions at `count` evenly spaced times
+/// positions are held flat before the first and after the last action
+fn sample(script: &FScript, start: i64, step: i64, count: usize) -> Vec<f64> {
+    let actions = &script.actions;
+    let mut out = Vec::with_capacity(count);
+    let mut idx = 0;
+    for i in 0..count {
+        let t = start + i as i64 * step;
+        while idx + 1 < actions.len() && actions[idx + 1].at as i64 <= t {
+            idx += 1;
+        }
+        let cur = &actions[idx];
+        let pos = match actions.get(idx + 1) {
+            Some(next) if t > cur.at as i64 => {
+                let span = (next.at - cur.at) as f64;
+                let frac = (t - cur.at as i64) as f64 / span;
+                cur.pos as f64 + (next.pos - cur.pos) as f64 * frac
+            }
+            _ => cur.pos as f64,
+        };
+        out.push(pos);
+    }
+    out
+}
+
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+    let mut cov = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        let dx = x - mean_a;
+        let dy = y - mean_b;
+        cov += dx * dy;
+        var_a += dx * dx;
+        var_b += dy * dy;
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        return 0.0;
+    }
+    cov / (var_a * var_b).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_funscript;
+
+    #[test]
+    fn test_estimate_offset() {
+        let path = "./test-scripts/openfunscripter.funscript";
+        let a = load_funscript(path).unwrap();
+        let mut b = load_funscript(path).unwrap();
+        for pt in &mut b.actions {
+            pt.at += 1370;
+        }
+
+        let est = estimate_offset(&a, &b, 3000, 20);
+        assert!((est.offset_ms - 1370).abs() <= 20, "got {}", est.offset_ms);
+        assert!(est.confident);
+        assert!(est.score > 0.9);
+
+        let back = estimate_offset(&b, &a, 3000, 20);
+        assert!((back.offset_ms + 1370).abs() <= 20);
+    }
+
+    #[test]
+    fn test_find_offset() {
+        let path = "./test-scripts/openfunscripter.funscript";
+        let a = load_funscript(path).unwrap();
+        let mut b = a.clone();
+        for pt in &mut b.actions {
+            pt.at -= 4230;
+        }
+        let est = find_offset(&a, &b, 10_000);
+        assert!((est.offset_ms + 4230).abs() <= 10, "got {}", est.offset_ms);
+        assert!(est.confident);
+
+        let edge = find_offset(&a, &b, 2000);
+        assert!(!edge.confident);
+        assert_eq!(find_offset(&a, &FScript::default(), 1000).offset_ms, 0);
+    }
+
+    #[test]
+    fn test_check_sync() {
+        let mut s = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        let report = check_sync(&s, Duration::from_secs(2610), 500);
+        assert!(report.in_sync, "{report:?}");
+        assert_eq!(report.metadata_drift_ms, Some(0));
+        assert!(!report.fix(&mut s));
+
+        // the same scene at 25 instead of 23.976 fps
+        let video = Duration::from_secs_f64(2610.0 * 23.976 / 25.0);
+        let report = check_sync(&s, video, 500);
+        assert!(!report.in_sync);
+        let last = report.last_action_ms;
+        assert!(report.fix(&mut s));
+        let fixed = check_sync(&s, video, 500);
+        assert!(fixed.in_sync, "{fixed:?}");
+        assert!(fixed.last_action_ms < last);
+    }
+
+    #[test]
+    fn test_similarity() {
+        let path = "./test-scripts/openfunscripter.funscript";
+        let a = load_funscript(path).unwrap();
+        assert!((similarity(&a, &a) - 1.0).abs() < 1e-6);
+
+        let mut inverted = a.clone();
+        crate::transform::invert(&mut inverted);
+        assert_eq!(similarity(&a, &inverted), 0.0);
+
+        let other = load_funscript("./test-scripts/scriptai-january-2022.funscript").unwrap();
+        assert!(similarity(&a, &other) < 0.3);
+        assert_eq!(similarity(&a, &FScript::default()), 0.0);
+    }
+
+    #[test]
+    fn test_dtw_similarity() {
+        let wave = |period: i32| {
+            let mut s = FScript::default();
+            for i in 0..40 {
+                let pos = if i % 2 == 0 { 0 } else { 100 };
+                s.actions.push(crate::FSPoint {
+                    at: i * period,
+                    pos,
+                });
+            }
+            s
+        };
+        let (a, mut b) = (wave(500), wave(500));
+        // the same strokes, a little late in the middle
+        for pt in &mut b.actions[10..30] {
+            pt.at += 120;
+        }
+        assert!(dtw_similarity(&a, &b, 20, 200) > 0.97);
+        assert!(dtw_similarity(&a, &b, 20, 200) > similarity(&a, &b));
+        assert!(dtw_similarity(&a, &wave(250), 20, 200) < 0.8);
+    }
+}
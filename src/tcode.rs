@@ -0,0 +1,434 @@
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Write};
+
+use crate::bundle::{Axis, FScriptBundle};
+use crate::player::{Sink, Tick};
+use crate::{FSPoint, FunscriptError};
+
+/// how script positions map onto one T-code channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelMapping {
+    /// the T-code channel, e.g. `L0` or `R1`
+    pub channel: String,
+    /// channel value for position 0, 0.0 to 1.0
+    pub min: f64,
+    /// channel value for position 100, 0.0 to 1.0
+    pub max: f64,
+}
+
+impl ChannelMapping {
+    pub fn new(channel: &str) -> Self {
+        Self {
+            channel: channel.to_string(),
+            min: 0.0,
+            max: 1.0,
+        }
+    }
+
+    /// scales a 0-100 position into the 0.0-1.0 channel range
+    pub fn scale(&self, pos: f64) -> f64 {
+        let frac = (pos / 100.0).clamp(0.0, 1.0);
+        (self.min + (self.max - self.min) * frac).clamp(0.0, 1.0)
+    }
+}
+
+/// axis to channel mapping and number formatting for T-code output
+#[derive(Debug, Clone, PartialEq)]
+pub struct TcodeOptions {
+    pub mappings: BTreeMap<Axis, ChannelMapping>,
+    /// digits used for channel values, 3 gives `L0500`
+    pub precision: u32,
+}
+
+impl Default for TcodeOptions {
+    /// the standard OSR2/SR6 layout
+    fn default() -> Self {
+        let mappings = [
+            (Axis::Stroke, "L0"),
+            (Axis::Surge, "L1"),
+            (Axis::Sway, "L2"),
+            (Axis::Twist, "R0"),
+            (Axis::Roll, "R1"),
+            (Axis::Pitch, "R2"),
+            (Axis::Vib, "V0"),
+            (Axis::Pump, "V1"),
+        ]
+        .into_iter()
+        .map(|(axis, channel)| (axis, ChannelMapping::new(channel)))
+        .collect();
+        Self {
+            mappings,
+            precision: 3,
+        }
+    }
+}
+
+/// a T-code line and the script time it should be sent at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcodeCommand {
+    pub at: i64,
+    pub line: String,
+}
+
+/// formats one channel command, e.g. `L0500I100`
+/// `value` is 0.0 to 1.0, the interval is how long the move should take
+pub fn command(channel: &str, value: f64, interval_ms: Option<i64>, precision: u32) -> String {
+    let scale = 10u64.pow(precision) - 1;
+    let digits = (value.clamp(0.0, 1.0) * scale as f64).round() as u64;
+    let mut out = format!("{channel}{digits:0width$}", width = precision as usize);
+    if let Some(interval) = interval_ms {
+        out.push_str(&format!("I{}", interval.max(0)));
+    }
+    out
+}
+
+/// converts action points into timed T-code commands for one channel
+/// the first point is sent at time 0 without an interval, then each command is sent
+/// when the previous point is reached and moves to the next one over the time between
+pub fn points_to_tcode(
+    actions: &[FSPoint],
+    mapping: &ChannelMapping,
+    precision: u32,
+) -> Vec<TcodeCommand> {
+    let Some(first) = actions.first() else {
+        return Vec::new();
+    };
+    let line = |pos: i32, interval| {
+        command(
+            &mapping.channel,
+            mapping.scale(pos as f64),
+            interval,
+            precision,
+        )
+    };
+    let mut out = Vec::with_capacity(actions.len());
+    out.push(TcodeCommand {
+        at: 0,
+        line: line(first.pos, None),
+    });
+    for pair in actions.windows(2) {
+        let (prev_at, at) = (pair[0].at as i64, pair[1].at as i64);
+        let cmd = TcodeCommand {
+            at: prev_at,
+            line: line(pair[1].pos, (at > prev_at).then_some(at - prev_at)),
+        };
+        // the device can only head for one target at a time, the later one wins
+        // but the starting position is always sent
+        match out[1..].last_mut() {
+            Some(last) if last.at == cmd.at => *last = cmd,
+            _ => out.push(cmd),
+        }
+    }
+    out
+}
+
+/// converts every mapped axis of a bundle into one chronological command stream
+/// commands for different channels sent at the same time share a line, the starting
+/// positions get a line of their own
+pub fn bundle_to_tcode(bundle: &FScriptBundle, options: &TcodeOptions) -> Vec<TcodeCommand> {
+    let mut by_time: BTreeMap<(i64, bool), Vec<String>> = BTreeMap::new();
+    for (axis, script) in bundle.iter() {
+        let Some(mapping) = options.mappings.get(axis) else {
+            continue;
+        };
+        let cmds = points_to_tcode(&script.resolved_actions(), mapping, options.precision);
+        for (i, cmd) in cmds.into_iter().enumerate() {
+            by_time.entry((cmd.at, i > 0)).or_default().push(cmd.line);
+        }
+    }
+    by_time
+        .into_iter()
+        .map(|((at, _), parts)| TcodeCommand {
+            at,
+            line: parts.join(" "),
+        })
+        .collect()
+}
+
+/// a player [`Sink`] that streams T-code to a device, e.g. an OSR2 on a usb serial port
+/// `connect` is called to open the port and again to reconnect after a write fails
+/// ticks are dropped instead of queued when the port can't keep up, a line the port
+/// only took part of is finished before the next one is sent
+pub struct TcodeSink<W, F> {
+    connect: F,
+    writer: Option<W>,
+    /// the unsent end of a partly written line
+    pending: Vec<u8>,
+    mapping: ChannelMapping,
+    precision: u32,
+    last_time: Option<i64>,
+    failures: usize,
+    /// consecutive failed connects or writes before playback is stopped
+    pub max_failures: usize,
+}
+
+impl<W, F> TcodeSink<W, F>
+where
+    W: Write + Send + 'static,
+    F: FnMut() -> std::io::Result<W> + Send + 'static,
+{
+    pub fn new(connect: F, mapping: ChannelMapping, precision: u32) -> Self {
+        Self {
+            connect,
+            writer: None,
+            pending: Vec::new(),
+            mapping,
+            precision,
+            last_time: None,
+            failures: 0,
+            max_failures: 10,
+        }
+    }
+
+    fn fail(&mut self, err: std::io::Error) -> Result<(), FunscriptError> {
+        self.writer = None;
+        self.pending.clear();
+        self.failures += 1;
+        if self.failures >= self.max_failures {
+            return Err(err.into());
+        }
+        Ok(())
+    }
+}
+
+impl<W, F> Sink for TcodeSink<W, F>
+where
+    W: Write + Send + 'static,
+    F: FnMut() -> std::io::Result<W> + Send + 'static,
+{
+    fn send(&mut self, tick: Tick) -> Result<(), FunscriptError> {
+        let interval = self.last_time.map(|last| tick.time_ms - last);
+        self.last_time = Some(tick.time_ms);
+
+        if self.writer.is_none() {
+            match (self.connect)() {
+                Ok(writer) => self.writer = Some(writer),
+                Err(err) => return self.fail(err),
+            }
+        }
+        let mut line = command(
+            &self.mapping.channel,
+            self.mapping.scale(tick.pos),
+            interval,
+            self.precision,
+        );
+        line.push('\n');
+
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+        if !self.pending.is_empty() {
+            let (written, result) = write_some(writer, &self.pending);
+            self.pending.drain(..written);
+            match result {
+                Ok(()) => {}
+                // still busy with the last line, skip this tick
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return self.fail(err),
+            }
+        }
+        let (written, result) = write_some(writer, line.as_bytes());
+        match result {
+            Ok(()) => {
+                self.failures = 0;
+                Ok(())
+            }
+            // the port is busy, skip this tick rather than fall behind, unless part
+            // of the line already went out
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                if written > 0 {
+                    self.pending = line.as_bytes()[written..].to_vec();
+                }
+                Ok(())
+            }
+            Err(err) => self.fail(err),
+        }
+    }
+}
+
+/// writes and flushes as much of `buf` as the writer takes, returns how many bytes
+/// were written along with the error that stopped it
+fn write_some<W: Write>(writer: &mut W, buf: &[u8]) -> (usize, std::io::Result<()>) {
+    let mut written = 0;
+    while written < buf.len() {
+        match writer.write(&buf[written..]) {
+            Ok(0) => return (written, Err(ErrorKind::WriteZero.into())),
+            Ok(n) => written += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return (written, Err(err)),
+        }
+    }
+    (written, writer.flush())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FScript;
+    use std::sync::{Arc, Mutex};
+
+    /// a fake port that records what was written and can be told to fail
+    #[derive(Clone, Default)]
+    struct Port {
+        written: Arc<Mutex<Vec<u8>>>,
+        fail: Arc<Mutex<Option<ErrorKind>>>,
+        /// bytes accepted before the port blocks, unlimited if `None`
+        budget: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl Write for Port {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if let Some(kind) = *self.fail.lock().unwrap() {
+                return Err(kind.into());
+            }
+            let mut budget = self.budget.lock().unwrap();
+            let n = budget.map_or(buf.len(), |left| left.min(buf.len()));
+            if let Some(left) = budget.as_mut() {
+                if *left == 0 {
+                    return Err(ErrorKind::WouldBlock.into());
+                }
+                *left -= n;
+            }
+            self.written.lock().unwrap().extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn tick(time_ms: i64, pos: f64) -> Tick {
+        Tick { time_ms, pos }
+    }
+
+    fn script(points: &[(i32, i32)]) -> FScript {
+        let mut s = FScript::default();
+        for &(at, pos) in points {
+            s.actions.push(FSPoint { at, pos });
+        }
+        s
+    }
+
+    #[test]
+    fn test_command_format() {
+        assert_eq!(command("L0", 0.5, Some(100), 3), "L0500I100");
+        assert_eq!(command("R1", 1.0, None, 4), "R19999");
+        assert_eq!(command("L0", 0.0, Some(-5), 2), "L000I0");
+
+        let half = ChannelMapping {
+            channel: "R0".to_string(),
+            min: 0.25,
+            max: 0.75,
+        };
+        assert_eq!(half.scale(0.0), 0.25);
+        assert_eq!(half.scale(100.0), 0.75);
+        assert_eq!(half.scale(50.0), 0.5);
+    }
+
+    #[test]
+    fn test_points_to_tcode() {
+        let s = script(&[(1000, 0), (1500, 100), (1600, 50)]);
+        let cmds = points_to_tcode(&s.actions, &ChannelMapping::new("L0"), 3);
+        let lines: Vec<_> = cmds.iter().map(|c| (c.at, c.line.as_str())).collect();
+        assert_eq!(
+            lines,
+            vec![(0, "L0000"), (1000, "L0999I500"), (1500, "L0500I100")]
+        );
+    }
+
+    #[test]
+    fn test_points_to_tcode_starts_at_first_point() {
+        let mut s = script(&[(0, 20), (100, 100), (300, 0)]);
+        let cmds = points_to_tcode(&s.actions, &ChannelMapping::new("L0"), 3);
+        assert_eq!(cmds.len(), 3);
+        assert_eq!(cmds[0].line, command("L0", 0.2, None, 3));
+        assert_eq!(cmds[1].at, 0);
+        assert_eq!(cmds[1].line, "L0999I100");
+
+        s.inverted = true;
+        let mut bundle = FScriptBundle::new();
+        bundle.insert(Axis::Stroke, s);
+        let cmds = bundle_to_tcode(&bundle, &TcodeOptions::default());
+        assert_eq!(cmds[0].line, command("L0", 0.8, None, 3));
+        assert_eq!(cmds[1].line, "L0000I100");
+    }
+
+    #[test]
+    fn test_tcode_sink() {
+        let port = Port::default();
+        let connects = Arc::new(Mutex::new(0));
+        let (p, c) = (port.clone(), Arc::clone(&connects));
+        let mut sink = TcodeSink::new(
+            move || {
+                *c.lock().unwrap() += 1;
+                Ok(p.clone())
+            },
+            ChannelMapping::new("L0"),
+            3,
+        );
+
+        sink.send(tick(0, 0.0)).unwrap();
+        sink.send(tick(20, 50.0)).unwrap();
+        *port.fail.lock().unwrap() = Some(ErrorKind::WouldBlock);
+        sink.send(tick(40, 60.0)).unwrap();
+        *port.fail.lock().unwrap() = Some(ErrorKind::BrokenPipe);
+        sink.send(tick(60, 70.0)).unwrap();
+        *port.fail.lock().unwrap() = None;
+        sink.send(tick(80, 100.0)).unwrap();
+
+        let written = String::from_utf8(port.written.lock().unwrap().clone()).unwrap();
+        assert_eq!(written, "L0000\nL0500I20\nL0999I20\n");
+        assert_eq!(*connects.lock().unwrap(), 2);
+
+        *port.fail.lock().unwrap() = Some(ErrorKind::BrokenPipe);
+        sink.max_failures = 3;
+        assert!(sink.send(tick(100, 0.0)).is_ok());
+        assert!(sink.send(tick(120, 0.0)).is_ok());
+        assert!(sink.send(tick(140, 0.0)).is_err());
+    }
+
+    #[test]
+    fn test_tcode_sink_partial_write() {
+        let port = Port::default();
+        let connects = Arc::new(Mutex::new(0));
+        let (p, c) = (port.clone(), Arc::clone(&connects));
+        let mut sink = TcodeSink::new(
+            move || {
+                *c.lock().unwrap() += 1;
+                Ok(p.clone())
+            },
+            ChannelMapping::new("L0"),
+            3,
+        );
+
+        sink.send(tick(0, 0.0)).unwrap();
+        *port.budget.lock().unwrap() = Some(3);
+        sink.send(tick(20, 50.0)).unwrap();
+        // the rest of the half written line is still stuck
+        sink.send(tick(40, 60.0)).unwrap();
+        *port.budget.lock().unwrap() = None;
+        sink.send(tick(60, 100.0)).unwrap();
+
+        let written = String::from_utf8(port.written.lock().unwrap().clone()).unwrap();
+        assert_eq!(written, "L0000\nL0500I20\nL0999I20\n");
+        assert_eq!(*connects.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_bundle_to_tcode() {
+        let mut bundle = FScriptBundle::new();
+        bundle.insert(Axis::Stroke, script(&[(0, 0), (100, 100), (300, 0)]));
+        bundle.insert(Axis::Roll, script(&[(0, 50), (200, 0)]));
+        bundle.insert(Axis::Other("lube".to_string()), script(&[(0, 10)]));
+
+        let cmds = bundle_to_tcode(&bundle, &TcodeOptions::default());
+        assert_eq!(cmds.len(), 3);
+        assert_eq!(cmds[0].at, 0);
+        assert_eq!(cmds[0].line, "L0000 R1500");
+        assert_eq!(cmds[1].at, 0);
+        assert_eq!(cmds[1].line, "L0999I100 R1000I200");
+        assert_eq!(cmds[2].at, 100);
+        assert_eq!(cmds[2].line, "L0000I200");
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{FScript, FunscriptError};
+
+/// how a template treats metadata that is already filled in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateMode {
+    /// only write fields that are currently empty
+    FillEmptyOnly,
+    /// always replace fields the template provides
+    Overwrite,
+}
+
+/// reusable metadata preset that can be stamped onto scripts
+/// string fields may contain `{title}` and `{filename}` placeholders
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MetadataTemplate {
+    pub creator: Option<String>,
+    pub license: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+    #[serde(rename = "type")]
+    pub ofs_type: Option<String>,
+    pub script_url: Option<String>,
+    pub video_url: Option<String>,
+}
+
+impl MetadataTemplate {
+    /// loads a template from a json file
+    pub fn load(path: &str) -> Result<Self, FunscriptError> {
+        let file = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file)?)
+    }
+
+    /// saves the template as a json file
+    pub fn save(&self, path: &str) -> Result<(), FunscriptError> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// applies the template to a script
+    /// `{filename}` falls back to the script title since no path is known
+    pub fn apply_to(&self, script: &mut FScript, mode: TemplateMode) {
+        self.apply_with_path(script, None, mode);
+    }
+
+    /// applies the template to a script, expanding `{filename}`
+    /// from the file stem of `path` (without `.funscript`)
+    pub fn apply_with_path(&self, script: &mut FScript, path: Option<&str>, mode: TemplateMode) {
+        let title = script.metadata.title.clone();
+        let filename = path.map(file_stem).unwrap_or_else(|| title.clone());
+        let expand = |s: &str| {
+            s.replace("{title}", &title)
+                .replace("{filename}", &filename)
+        };
+
+        let meta = &mut script.metadata;
+        let fields = [
+            (&mut meta.creator, &self.creator),
+            (&mut meta.license, &self.license),
+            (&mut meta.notes, &self.notes),
+            (&mut meta.ofs_type, &self.ofs_type),
+            (&mut meta.script_url, &self.script_url),
+            (&mut meta.video_url, &self.video_url),
+        ];
+        for (field, value) in fields {
+            if let Some(value) = value {
+                if mode == TemplateMode::Overwrite || field.is_empty() {
+                    *field = expand(value);
+                }
+            }
+        }
+
+        if !self.tags.is_empty() && (mode == TemplateMode::Overwrite || meta.tags.is_empty()) {
+            meta.tags = self.tags.iter().map(|t| expand(t)).collect();
+        }
+    }
+}
+
+/// file name of a script path without directories or the .funscript extension
+fn file_stem(path: &str) -> String {
+    let name = std::path::Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path);
+    name.strip_suffix(".funscript").unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_funscript;
+
+    fn template() -> MetadataTemplate {
+        MetadataTemplate {
+            creator: Some("kallo".to_string()),
+            license: Some("CC-BY".to_string()),
+            notes: Some("scripted for {title}".to_string()),
+            tags: vec!["vr".to_string(), "{filename}".to_string()],
+            ofs_type: Some("premium".to_string()),
+            script_url: Some("https://example.com/scripts/{filename}".to_string()),
+            video_url: None,
+        }
+    }
+
+    #[test]
+    fn test_template_placeholders() {
+        let path = "./test-scripts/openfunscripter.funscript";
+        let mut s = load_funscript(path).unwrap();
+        s.metadata.title = "Scene One".to_string();
+        template().apply_with_path(&mut s, Some(path), TemplateMode::FillEmptyOnly);
+
+        assert_eq!(s.metadata.creator, "kallo");
+        assert_eq!(s.metadata.notes, "scripted for Scene One");
+        assert_eq!(s.metadata.tags, vec!["vr", "openfunscripter"]);
+        assert_eq!(
+            s.metadata.script_url,
+            "https://example.com/scripts/openfunscripter"
+        );
+        assert_eq!(s.metadata.video_url, "");
+    }
+
+    #[test]
+    fn test_template_fill_empty_only() {
+        let path = "./test-scripts/openfunscripter.funscript";
+        let mut s = load_funscript(path).unwrap();
+        s.metadata.creator = "someone else".to_string();
+        template().apply_to(&mut s, TemplateMode::FillEmptyOnly);
+        assert_eq!(s.metadata.creator, "someone else");
+        assert_eq!(s.metadata.ofs_type, "basic");
+        assert_eq!(s.metadata.license, "CC-BY");
+
+        template().apply_to(&mut s, TemplateMode::Overwrite);
+        assert_eq!(s.metadata.creator, "kallo");
+        assert_eq!(s.metadata.ofs_type, "premium");
+    }
+
+    #[test]
+    fn test_template_save_load() {
+        std::fs::create_dir_all("./test-scripts/out").unwrap();
+        let save_path = "./test-scripts/out/template.json";
+        template().save(save_path).unwrap();
+        let check = MetadataTemplate::load(save_path).unwrap();
+        assert_eq!(check.creator.as_deref(), Some("kallo"));
+        assert_eq!(check.tags.len(), 2);
+    }
+}
@@ -0,0 +1,1264 @@
+use crate::analysis::{find_gaps, Gap};
+use crate::{interpolate, Chapter, FSPoint, FScript};
+
+/// how many points were clamped by [`offset_positions`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OffsetReport {
+    /// points that would have gone below 0
+    pub clamped_low: usize,
+    /// points that would have gone above 100
+    pub clamped_high: usize,
+}
+
+/// adds `delta` to every position, clamping to 0-100
+/// clamping loses shape and can't be undone, the report says how much was lost
+pub fn offset_positions(script: &mut FScript, delta: i32, include_raw: bool) -> OffsetReport {
+    let mut report = OffsetReport::default();
+    offset_points(&mut script.actions, delta, &mut report);
+    if include_raw {
+        offset_points(&mut script.raw_actions, delta, &mut report);
+    }
+    report
+}
+
+fn offset_points(points: &mut [FSPoint], delta: i32, report: &mut OffsetReport) {
+    for pt in points {
+        let pos = pt.pos + delta;
+        if pos < 0 {
+            report.clamped_low += 1;
+        } else if pos > 100 {
+            report.clamped_high += 1;
+        }
+        pt.pos = pos.clamp(0, 100);
+    }
+}
+
+/// mirrors every position, `pos` becomes `100 - pos`, for actions and raw actions
+/// the `inverted` flag is left alone, see [`FScript::resolved_actions`] for honoring it
+pub fn invert(script: &mut FScript) {
+    for pt in script
+        .actions
+        .iter_mut()
+        .chain(script.raw_actions.iter_mut())
+    {
+        pt.pos = 100 - pt.pos;
+    }
+}
+
+/// multiplies each action's distance from `center` by `factor`, clamping to 0-100
+/// a factor below 1 softens the script, above 1 intensifies it
+pub fn scale_amplitude(script: &mut FScript, factor: f64, center: f64) -> OffsetReport {
+    let mut report = OffsetReport::default();
+    for pt in script.actions.iter_mut() {
+        let pos = (center + (pt.pos as f64 - center) * factor).round();
+        if pos < 0.0 {
+            report.clamped_low += 1;
+        } else if pos > 100.0 {
+            report.clamped_high += 1;
+        }
+        pt.pos = pos.clamp(0.0, 100.0) as i32;
+    }
+    report
+}
+
+/// linearly rescales the actions so their lowest and highest positions become 0 and 100
+/// with `expand_only` a script already reaching past 0-100 is left as is instead of compressed
+pub fn normalize_range(script: &mut FScript, expand_only: bool) {
+    let min = script.actions.iter().map(|pt| pt.pos).min();
+    let max = script.actions.iter().map(|pt| pt.pos).max();
+    let (Some(min), Some(max)) = (min, max) else {
+        return;
+    };
+    if max == min || (expand_only && max - min > 100) {
+        return;
+    }
+    let scale = 100.0 / (max - min) as f64;
+    for pt in script.actions.iter_mut() {
+        pt.pos = ((pt.pos - min) as f64 * scale).round() as i32;
+    }
+}
+
+/// motion generated by [`fill_gaps`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillPattern {
+    /// stay at the position before the gap until the motion resumes
+    Hold,
+    /// slow strokes of `amplitude` around the middle of the range
+    SlowWave { period_ms: i64, amplitude: i32 },
+    /// ease to `rest`, stay there and return for the next action
+    Fade { rest: i32 },
+}
+
+/// which gaps [`fill_gaps`] fills
+/// call it once per length range to use a different pattern for short and long gaps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillOptions {
+    pub min_gap_ms: i64,
+    /// longer gaps are left alone, e.g. intentional breaks
+    pub max_gap_ms: Option<i64>,
+    /// time kept free at both ends of a gap so the pattern doesn't start abruptly
+    pub margin_ms: i64,
+}
+
+impl Default for FillOptions {
+    fn default() -> Self {
+        Self {
+            min_gap_ms: 5000,
+            max_gap_ms: None,
+            margin_ms: 500,
+        }
+    }
+}
+
+/// inserts generated motion into the silent stretches between actions
+/// a stretch is one where the position holds, see [`find_gaps`], slow moves are left
+/// alone and only gaps between two actions are filled, returns how many were filled
+pub fn fill_gaps(script: &mut FScript, pattern: FillPattern, options: FillOptions) -> usize {
+    let (Some(first), Some(last)) = (script.actions.first(), script.actions.last()) else {
+        return 0;
+    };
+    let (first, last) = (first.at as i64, last.at as i64);
+    let mut gaps = find_gaps(script, options.min_gap_ms.max(1))
+        .into_iter()
+        .filter(|gap| gap.start >= first && gap.end <= last)
+        .filter(|gap| {
+            options
+                .max_gap_ms
+                .is_none_or(|max| gap.duration_ms() <= max)
+        })
+        .filter(|gap| gap.end - gap.start > 2 * options.margin_ms)
+        .peekable();
+
+    let mut filled = 0;
+    let mut current: Option<Gap> = None;
+    let mut out: Vec<FSPoint> = Vec::with_capacity(script.actions.len());
+    for (i, pt) in script.actions.iter().enumerate() {
+        let at = pt.at as i64;
+        // the held points inside a filled gap are replaced by the pattern
+        if current.is_some_and(|gap| at >= gap.start && at < gap.end) {
+            continue;
+        }
+        out.push(pt.clone());
+        let starts_gap = gaps.peek().is_some_and(|gap| gap.start == at)
+            && script
+                .actions
+                .get(i + 1)
+                .is_none_or(|next| next.at as i64 > at);
+        if !starts_gap {
+            continue;
+        }
+        let Some(gap) = gaps.next() else {
+            continue;
+        };
+        let start = gap.start + options.margin_ms;
+        let end = gap.end - options.margin_ms;
+        fill_between(&mut out, start, end, pt.pos, pattern);
+        current = Some(gap);
+        filled += 1;
+    }
+    script.actions = out;
+    filled
+}
+
+/// pushes the points of `pattern` for a gap held at `pos` from `start` to `end`
+fn fill_between(out: &mut Vec<FSPoint>, start: i64, end: i64, pos: i32, pattern: FillPattern) {
+    let at = |t: i64| t as i32;
+    match pattern {
+        FillPattern::Hold => out.push(FSPoint { at: at(end), pos }),
+        FillPattern::Fade { rest } => {
+            let rest = rest.clamp(0, 100);
+            let ease = ((end - start) / 4).max(1);
+            out.push(FSPoint {
+                at: at(start + ease),
+                pos: rest,
+            });
+            out.push(FSPoint {
+                at: at(end - ease),
+                pos: rest,
+            });
+        }
+        FillPattern::SlowWave {
+            period_ms,
+            amplitude,
+        } => {
+            let half_period = (period_ms / 2).max(1);
+            let amplitude = amplitude.clamp(0, 100);
+            let (low, high) = (50 - amplitude / 2, 50 + (amplitude + 1) / 2);
+            let mut t = start;
+            let mut up = pos < 50;
+            while t <= end {
+                out.push(FSPoint {
+                    at: at(t),
+                    pos: if up { high } else { low },
+                });
+                up = !up;
+                t += half_period;
+            }
+        }
+    }
+}
+
+/// multiplies every time in the script by `factor`, e.g. 25.0 / 23.976 after a frame rate change
+/// actions, raw actions, bookmarks, chapters and the duration are scaled,
+/// actions rounded onto the same ms are merged keeping the later one
+pub fn time_scale(script: &mut FScript, factor: f64) {
+    if !(factor.is_finite() && factor > 0.0) {
+        return;
+    }
+    let scale = |t: i64| (t as f64 * factor).round() as i64;
+    for points in [&mut script.actions, &mut script.raw_actions] {
+        for pt in points.iter_mut() {
+            pt.at = scale(pt.at as i64) as i32;
+        }
+        points.dedup_by(|later, earlier| {
+            let same = later.at == earlier.at;
+            if same {
+                earlier.pos = later.pos;
+            }
+            same
+        });
+    }
+    if script.bookmark >= 0 {
+        script.bookmark = scale(script.bookmark as i64) as i32;
+    }
+    for b in script.metadata.bookmarks.iter_mut() {
+        *b = scale(*b as i64) as i32;
+    }
+    for chapter in script.metadata.chapters_mut() {
+        chapter.start_time = scale(chapter.start_time);
+        chapter.end_time = scale(chapter.end_time);
+    }
+    if script.metadata.duration > 0 {
+        script.metadata.duration = (script.metadata.duration as f64 * factor).ceil() as i32;
+    }
+}
+
+/// plays the script backwards, every time `t` becomes `duration - t`
+/// the duration is the metadata duration if set, otherwise the last action,
+/// bookmarks and chapters are mirrored too
+pub fn reverse(script: &mut FScript) {
+    let length = source_length(script);
+    let mirror = |t: i64| length - t;
+    for points in [&mut script.actions, &mut script.raw_actions] {
+        points.reverse();
+        for pt in points.iter_mut() {
+            pt.at = mirror(pt.at as i64) as i32;
+        }
+    }
+    if script.bookmark >= 0 {
+        script.bookmark = mirror(script.bookmark as i64).max(0) as i32;
+    }
+    let bookmarks = &mut script.metadata.bookmarks;
+    for b in bookmarks.iter_mut() {
+        *b = mirror(*b as i64) as i32;
+    }
+    bookmarks.sort_unstable();
+    let chapters = script.metadata.chapters_mut();
+    for chapter in chapters.iter_mut() {
+        let start = mirror(chapter.end_time);
+        chapter.end_time = mirror(chapter.start_time);
+        chapter.start_time = start;
+    }
+    chapters.reverse();
+}
+
+/// replaces the actions with evenly spaced points every `interval_ms`
+/// positions are interpolated, the last action is kept so the script length doesn't change
+pub fn resample(script: &mut FScript, interval_ms: i64) {
+    let (Some(first), Some(last)) = (script.actions.first(), script.actions.last()) else {
+        return;
+    };
+    if interval_ms <= 0 {
+        return;
+    }
+    let (start, end) = (first.at as i64, last.at as i64);
+    let mut points = Vec::with_capacity(((end - start) / interval_ms + 2) as usize);
+    let mut t = start;
+    while t <= end {
+        points.push(sample_point(&script.actions, t));
+        t += interval_ms;
+    }
+    if t - interval_ms < end {
+        points.push(sample_point(&script.actions, end));
+    }
+    script.actions = points;
+}
+
+/// what [`shift`] does with points that would end up before 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NegativeTimes {
+    /// replace them with one interpolated point at 0 so the start of the motion is kept
+    #[default]
+    Clamp,
+    /// remove them
+    Drop,
+}
+
+/// points affected by [`shift`] going below 0
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ShiftReport {
+    pub clamped: usize,
+    pub dropped: usize,
+}
+
+/// moves the whole script by `offset_ms`, a negative offset makes it play earlier
+/// actions, raw actions, bookmarks and chapters are all moved
+pub fn shift(script: &mut FScript, offset_ms: i64, negative: NegativeTimes) -> ShiftReport {
+    let mut report = ShiftReport::default();
+    shift_points(&mut script.actions, offset_ms, negative, &mut report);
+    shift_points(&mut script.raw_actions, offset_ms, negative, &mut report);
+
+    let moved = |t: i32| (t as i64 + offset_ms).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+    if script.bookmark >= 0 {
+        script.bookmark = moved(script.bookmark).max(0);
+    }
+    let bookmarks = &mut script.metadata.bookmarks;
+    *bookmarks = bookmarks
+        .iter()
+        .map(|&b| moved(b))
+        .filter(|&b| b >= 0)
+        .collect();
+
+    let chapters = script.metadata.chapters_mut();
+    for chapter in chapters.iter_mut() {
+        chapter.start_time = (chapter.start_time + offset_ms).max(0);
+        chapter.end_time = (chapter.end_time + offset_ms).max(0);
+    }
+    chapters.retain(|c| c.end_time > 0);
+    report
+}
+
+fn shift_points(
+    points: &mut Vec<FSPoint>,
+    offset_ms: i64,
+    negative: NegativeTimes,
+    report: &mut ShiftReport,
+) {
+    let below = points.partition_point(|pt| (pt.at as i64) + offset_ms < 0);
+    let mut start = None;
+    if below > 0 {
+        match negative {
+            NegativeTimes::Drop => report.dropped += below,
+            NegativeTimes::Clamp => {
+                report.clamped += below;
+                start = Some(sample_point(points, -offset_ms));
+            }
+        }
+    }
+
+    points.drain(..below);
+    for pt in points.iter_mut() {
+        pt.at = (pt.at as i64 + offset_ms) as i32;
+    }
+    if let Some(mut pt) = start {
+        if points.first().is_none_or(|first| first.at != 0) {
+            pt.at = 0;
+            points.insert(0, pt);
+        }
+    }
+}
+
+/// cuts the script down to `start_ms..=end_ms` and rebases it to start at 0
+/// points are interpolated at the window edges so the motion at the cut is kept,
+/// chapters are trimmed to the window and bookmarks outside it are dropped
+pub fn crop(script: &mut FScript, start_ms: i64, end_ms: i64) {
+    if end_ms < start_ms {
+        return;
+    }
+    crop_points(&mut script.actions, start_ms, end_ms);
+    crop_points(&mut script.raw_actions, start_ms, end_ms);
+
+    let rebase = |t: i32| {
+        (start_ms..=end_ms)
+            .contains(&(t as i64))
+            .then(|| (t as i64 - start_ms) as i32)
+    };
+    if script.bookmark >= 0 {
+        script.bookmark = rebase(script.bookmark).unwrap_or(-1);
+    }
+    let bookmarks = &mut script.metadata.bookmarks;
+    *bookmarks = bookmarks.iter().filter_map(|&b| rebase(b)).collect();
+
+    let chapters = script.metadata.chapters_mut();
+    chapters.retain(|c| c.end_time > start_ms && c.start_time <= end_ms);
+    for chapter in chapters.iter_mut() {
+        chapter.start_time = chapter.start_time.max(start_ms) - start_ms;
+        chapter.end_time = chapter.end_time.min(end_ms) - start_ms;
+    }
+
+    // metadata duration is in whole seconds
+    let length = ((end_ms - start_ms + 999) / 1000) as i32;
+    if script.metadata.duration > length {
+        script.metadata.duration = length;
+    }
+}
+
+fn crop_points(points: &mut Vec<FSPoint>, start_ms: i64, end_ms: i64) {
+    if points.is_empty() {
+        return;
+    }
+    let lo = points.partition_point(|pt| (pt.at as i64) < start_ms);
+    let hi = points.partition_point(|pt| (pt.at as i64) <= end_ms);
+    let mut cropped = Vec::with_capacity(hi.saturating_sub(lo) + 2);
+    if points.get(lo).is_none_or(|pt| pt.at as i64 != start_ms) {
+        cropped.push(sample_point(points, start_ms));
+    }
+    cropped.extend_from_slice(&points[lo..hi.max(lo)]);
+    if cropped.last().is_some_and(|pt| (pt.at as i64) < end_ms) {
+        cropped.push(sample_point(points, end_ms));
+    }
+    for pt in cropped.iter_mut() {
+        pt.at = (pt.at as i64 - start_ms) as i32;
+    }
+    *points = cropped;
+}
+
+/// joins scripts end to end with `gap_ms` of silence between them
+/// each source takes up its metadata duration if set, otherwise up to its last action,
+/// and becomes one chapter named after its title; tags and performers are merged
+pub fn concat(scripts: &[FScript], gap_ms: i64) -> FScript {
+    concat_refs(&scripts.iter().collect::<Vec<_>>(), gap_ms)
+}
+
+fn concat_refs(scripts: &[&FScript], gap_ms: i64) -> FScript {
+    let mut out = FScript::default();
+    if let Some(first) = scripts.first() {
+        out.version = first.version.clone();
+        out.inverted = first.inverted;
+        out.range = first.range;
+    }
+
+    let mut offset = 0i64;
+    let mut known_duration = true;
+    for (i, script) in scripts.iter().enumerate() {
+        let meta = &script.metadata;
+        known_duration &= meta.duration > 0;
+        let length = source_length(script);
+
+        out.actions.extend(moved_points(&script.actions, offset));
+        out.raw_actions
+            .extend(moved_points(&script.raw_actions, offset));
+        out.metadata
+            .bookmarks
+            .extend(meta.bookmarks.iter().map(|&b| (b as i64 + offset) as i32));
+
+        let name = match meta.title.trim() {
+            "" => format!("part {}", i + 1),
+            title => title.to_string(),
+        };
+        out.metadata.chapters_mut().push(Chapter {
+            name,
+            start_time: offset,
+            end_time: offset + length,
+        });
+        for tag in &meta.tags {
+            if !out.metadata.tags.contains(tag) {
+                out.metadata.tags.push(tag.clone());
+            }
+        }
+        for performer in &meta.performers {
+            if !out.metadata.performers.contains(performer) {
+                out.metadata.performers.push(performer.clone());
+            }
+        }
+        offset += length + gap_ms;
+    }
+
+    if known_duration && !scripts.is_empty() {
+        out.metadata.duration = ((offset - gap_ms + 999) / 1000) as i32;
+    }
+    out
+}
+
+/// inserts `insert` into `target` at `at_ms`, pushing everything after it back
+/// the target's motion is cut at `at_ms` with interpolated points on both sides,
+/// where times collide the inserted point wins so actions stay strictly increasing
+pub fn splice(target: &mut FScript, insert: &FScript, at_ms: i64) {
+    let length = source_length(insert);
+    splice_points(&mut target.actions, &insert.actions, at_ms, length);
+    splice_points(&mut target.raw_actions, &insert.raw_actions, at_ms, length);
+
+    let later = |t: i64| if t >= at_ms { t + length } else { t };
+    if target.bookmark >= 0 {
+        target.bookmark = later(target.bookmark as i64) as i32;
+    }
+    for b in target.metadata.bookmarks.iter_mut() {
+        *b = later(*b as i64) as i32;
+    }
+    for chapter in target.metadata.chapters_mut() {
+        chapter.start_time = later(chapter.start_time);
+        // a chapter running across the cut grows to include the insert
+        chapter.end_time = later(chapter.end_time);
+    }
+    if target.metadata.duration > 0 {
+        target.metadata.duration += ((length + 999) / 1000) as i32;
+    }
+}
+
+fn splice_points(points: &mut Vec<FSPoint>, insert: &[FSPoint], at_ms: i64, length: i64) {
+    let split = points.partition_point(|pt| (pt.at as i64) < at_ms);
+    let inside = split > 0 && split < points.len();
+    let mut out = Vec::with_capacity(points.len() + insert.len() + 2);
+    out.extend_from_slice(&points[..split]);
+    if inside && insert.first().is_none_or(|pt| pt.at != 0) {
+        out.push(sample_point(points, at_ms));
+    }
+    out.extend(moved_points(insert, at_ms));
+    if inside && points[split].at as i64 != at_ms {
+        let mut pt = sample_point(points, at_ms);
+        pt.at = (at_ms + length) as i32;
+        out.push(pt);
+    }
+    out.extend(moved_points(&points[split..], length));
+    out.dedup_by(|later, earlier| later.at == earlier.at);
+    *points = out;
+}
+
+/// joins `b` onto the end of `a` with the last `overlap_ms` of `a` blended into `b`
+/// positions in the overlap move linearly from following `a` to following `b`,
+/// metadata is merged the same way as [`concat`]
+pub fn crossfade(a: &FScript, b: &FScript, overlap_ms: i64) -> FScript {
+    let overlap = overlap_ms.clamp(0, source_length(a).min(source_length(b)));
+    let mut out = concat_refs(&[a, b], -overlap);
+    let offset = source_length(a) - overlap;
+    out.actions = blend(&a.actions, &b.actions, offset, overlap);
+    out.raw_actions = blend(&a.raw_actions, &b.raw_actions, offset, overlap);
+    out
+}
+
+fn blend(a: &[FSPoint], b: &[FSPoint], offset: i64, overlap: i64) -> Vec<FSPoint> {
+    let b: Vec<FSPoint> = moved_points(b, offset).collect();
+    let (start, end) = (offset, offset + overlap);
+    let mut out: Vec<FSPoint> = a
+        .iter()
+        .filter(|pt| (pt.at as i64) < start)
+        .cloned()
+        .collect();
+    if a.is_empty() || b.is_empty() || overlap == 0 {
+        out.extend(b.into_iter().filter(|pt| (pt.at as i64) >= start));
+        return out;
+    }
+
+    // every point of either script inside the overlap plus both edges
+    let mut times: Vec<i64> = a
+        .iter()
+        .chain(&b)
+        .map(|pt| pt.at as i64)
+        .filter(|t| (start..=end).contains(t))
+        .chain([start, end])
+        .collect();
+    times.sort_unstable();
+    times.dedup();
+    for t in times {
+        let weight = (t - start) as f64 / overlap as f64;
+        let from = interpolate(a, t).unwrap_or(0.0);
+        let to = interpolate(&b, t).unwrap_or(0.0);
+        out.push(FSPoint {
+            at: t as i32,
+            pos: (from + (to - from) * weight).round() as i32,
+        });
+    }
+    out.extend(b.into_iter().filter(|pt| pt.at as i64 > end));
+    out
+}
+
+/// plays `start_ms..=end_ms` `count` times in a row, everything after it moves back
+/// each repeat leaves out the start point so the end point before it is kept
+/// a count of 1 leaves the script unchanged
+pub fn loop_section(script: &mut FScript, start_ms: i64, end_ms: i64, count: usize) {
+    if end_ms <= start_ms || count < 2 {
+        return;
+    }
+    // cropping puts a point at both edges, so the copy is exactly as long as the section
+    let mut section = FScript {
+        actions: script.actions.clone(),
+        raw_actions: script.raw_actions.clone(),
+        ..Default::default()
+    };
+    for points in [&mut section.actions, &mut section.raw_actions] {
+        crop_points(points, start_ms, end_ms);
+        if !points.is_empty() {
+            points.remove(0);
+        }
+    }
+
+    let length = end_ms - start_ms;
+    for i in 1..count {
+        splice(script, &section, end_ms + (i as i64 - 1) * length);
+    }
+}
+
+/// how [`merge_overlay`] combines scripts that are active at the same time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// the mean position of every script active at that time
+    #[default]
+    Average,
+    /// the highest position of every script active at that time
+    Max,
+    /// the first script in the list that is active at that time
+    Priority,
+}
+
+/// combines scripts of the same video into one
+/// a script is active from its first to its last action, the result has a point at
+/// every action time of every script and keeps the metadata of the first script
+pub fn merge_overlay(scripts: &[FScript], strategy: MergeStrategy) -> FScript {
+    let Some(first) = scripts.first() else {
+        return FScript::default();
+    };
+    let mut times: Vec<i32> = scripts
+        .iter()
+        .flat_map(|s| s.actions.iter().map(|pt| pt.at))
+        .collect();
+    times.sort_unstable();
+    times.dedup();
+
+    let active = |s: &FScript, t: i32| match (s.actions.first(), s.actions.last()) {
+        (Some(a), Some(b)) if (a.at..=b.at).contains(&t) => interpolate(&s.actions, t as i64),
+        _ => None,
+    };
+    let mut out = FScript {
+        actions: Vec::with_capacity(times.len()),
+        raw_actions: Vec::new(),
+        ..first.clone()
+    };
+    for t in times {
+        let mut values = scripts.iter().filter_map(|s| active(s, t));
+        let pos = match strategy {
+            MergeStrategy::Priority => values.next(),
+            MergeStrategy::Max => values.reduce(f64::max),
+            MergeStrategy::Average => {
+                let (sum, n) = values.fold((0.0, 0), |(sum, n), v| (sum + v, n + 1));
+                (n > 0).then(|| sum / n as f64)
+            }
+        };
+        if let Some(pos) = pos {
+            out.actions.push(FSPoint {
+                at: t,
+                pos: pos.round() as i32,
+            });
+        }
+    }
+    out
+}
+
+/// one script per chapter, cropped to the chapter and rebased to start at 0
+/// the metadata is kept and the chapter name is added to the title
+pub fn split_by_chapters(script: &FScript) -> Vec<(Chapter, FScript)> {
+    script
+        .metadata
+        .chapters()
+        .iter()
+        .map(|chapter| {
+            let part = part(script, chapter.start_time, chapter.end_time, &chapter.name);
+            (chapter.clone(), part)
+        })
+        .collect()
+}
+
+/// cuts the script at each of `times_ms` into rebased parts titled `part N`
+/// cut times outside the script are ignored, the last part runs to the end of the
+/// script as measured by its metadata duration or last action
+pub fn split_at(script: &FScript, times_ms: &[i64]) -> Vec<FScript> {
+    let length = source_length(script);
+    let mut cuts: Vec<i64> = times_ms
+        .iter()
+        .copied()
+        .filter(|&t| t > 0 && t < length)
+        .collect();
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut edges = vec![0];
+    edges.extend(cuts);
+    edges.push(length);
+    edges
+        .windows(2)
+        .enumerate()
+        .map(|(i, w)| part(script, w[0], w[1], &format!("part {}", i + 1)))
+        .collect()
+}
+
+/// splits the script into `parts` parts of equal length, see [`split_at`]
+pub fn split_equal(script: &FScript, parts: usize) -> Vec<FScript> {
+    let length = source_length(script);
+    let parts = parts.max(1) as i64;
+    let cuts: Vec<i64> = (1..parts).map(|i| length * i / parts).collect();
+    split_at(script, &cuts)
+}
+
+/// `script` cropped to `start_ms..=end_ms` with `name` added to its title
+fn part(script: &FScript, start_ms: i64, end_ms: i64, name: &str) -> FScript {
+    let mut part = script.clone();
+    crop(&mut part, start_ms, end_ms);
+    let title = &mut part.metadata.title;
+    *title = match (title.is_empty(), name.is_empty()) {
+        (_, true) => title.clone(),
+        (true, false) => name.to_string(),
+        (false, false) => format!("{title} - {name}"),
+    };
+    part
+}
+
+/// how much time a script takes up when joined with others
+pub(crate) fn source_length(script: &FScript) -> i64 {
+    let last = crate::stats::duration_ms(script);
+    match script.metadata.duration {
+        d if d > 0 => (d as i64 * 1000).max(last),
+        _ => last,
+    }
+}
+
+fn moved_points(points: &[FSPoint], offset_ms: i64) -> impl Iterator<Item = FSPoint> + '_ {
+    points.iter().map(move |pt| FSPoint {
+        at: (pt.at as i64 + offset_ms) as i32,
+        pos: pt.pos,
+    })
+}
+
+fn sample_point(actions: &[FSPoint], t: i64) -> FSPoint {
+    FSPoint {
+        at: t as i32,
+        pos: interpolate(actions, t).unwrap_or(0.0).round() as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_range() -> FScript {
+        let mut s = FScript::default();
+        for i in 0..=10 {
+            s.actions.push(FSPoint {
+                at: i * 100,
+                pos: i * 10,
+            });
+            s.raw_actions.push(FSPoint {
+                at: i * 100,
+                pos: i * 10,
+            });
+        }
+        s
+    }
+
+    #[test]
+    fn test_offset_positions() {
+        let mut s = full_range();
+        let report = offset_positions(&mut s, 30, false);
+        assert_eq!(
+            report,
+            OffsetReport {
+                clamped_low: 0,
+                clamped_high: 3
+            }
+        );
+        assert_eq!(s.actions[0].pos, 30);
+        assert_eq!(s.actions[10].pos, 100);
+        assert_eq!(s.raw_actions[0].pos, 0);
+
+        let report = offset_positions(&mut s, -50, true);
+        assert_eq!(report.clamped_low, 2 + 5);
+        assert_eq!(s.raw_actions[10].pos, 50);
+    }
+
+    #[test]
+    fn test_resample() {
+        let mut s = FScript::default();
+        s.actions.push(FSPoint { at: 0, pos: 0 });
+        s.actions.push(FSPoint { at: 95, pos: 95 });
+        s.actions.push(FSPoint { at: 205, pos: 0 });
+        resample(&mut s, 20);
+
+        let times: Vec<_> = s.actions.iter().map(|pt| pt.at).collect();
+        let expected: Vec<_> = (0..=200).step_by(20).chain([205]).collect();
+        assert_eq!(times, expected);
+        assert_eq!(s.actions[2].pos, 40);
+        assert_eq!(s.actions[5].pos, 95 - 5 * 95 / 110);
+        assert_eq!(s.actions.last().unwrap().pos, 0);
+
+        let mut empty = FScript::default();
+        resample(&mut empty, 10);
+        assert!(empty.actions.is_empty());
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut s = full_range();
+        s.bookmark = 50;
+        s.metadata.bookmarks = vec![100, 900];
+        s.metadata.chapters_mut().push(Chapter {
+            name: "intro".to_string(),
+            start_time: 0,
+            end_time: 200,
+        });
+
+        let report = shift(&mut s, 250, NegativeTimes::Clamp);
+        assert_eq!(report, ShiftReport::default());
+        assert_eq!(s.actions[0].at, 250);
+        assert_eq!(s.raw_actions[10].at, 1250);
+        assert_eq!(s.metadata.bookmarks, vec![350, 1150]);
+        assert_eq!(s.metadata.chapters()[0].end_time, 450);
+
+        let report = shift(&mut s, -500, NegativeTimes::Clamp);
+        assert_eq!(report.clamped, 3 * 2);
+        assert_eq!(s.actions.len(), 9);
+        assert_eq!((s.actions[0].at, s.actions[0].pos), (0, 25));
+        assert_eq!(s.actions[1].at, 50);
+        assert_eq!(s.bookmark, 0);
+        assert_eq!(s.metadata.bookmarks, vec![650]);
+        assert!(s.metadata.chapters().is_empty());
+
+        let mut s = full_range();
+        let report = shift(&mut s, -200, NegativeTimes::Drop);
+        assert_eq!(report.dropped, 2 * 2);
+        assert_eq!((s.actions[0].at, s.actions[0].pos), (0, 20));
+    }
+
+    #[test]
+    fn test_crop() {
+        let mut s = full_range();
+        s.metadata.bookmarks = vec![100, 400, 900];
+        s.metadata.duration = 2;
+        s.metadata.chapters_mut().extend([
+            Chapter {
+                name: "a".to_string(),
+                start_time: 0,
+                end_time: 300,
+            },
+            Chapter {
+                name: "b".to_string(),
+                start_time: 300,
+                end_time: 1000,
+            },
+        ]);
+
+        crop(&mut s, 250, 600);
+        let points: Vec<_> = s.actions.iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(
+            points,
+            vec![(0, 25), (50, 30), (150, 40), (250, 50), (350, 60)]
+        );
+        assert_eq!(s.raw_actions.len(), 5);
+        assert_eq!(s.metadata.bookmarks, vec![150]);
+        assert_eq!(s.metadata.duration, 1);
+        let chapters: Vec<_> = s
+            .metadata
+            .chapters()
+            .iter()
+            .map(|c| (c.start_time, c.end_time))
+            .collect();
+        assert_eq!(chapters, vec![(0, 50), (50, 350)]);
+    }
+
+    #[test]
+    fn test_concat() {
+        let mut a = full_range();
+        a.metadata.title = "first".to_string();
+        a.metadata.tags = vec!["loop".to_string()];
+        let mut b = full_range();
+        b.metadata.duration = 2;
+        b.metadata.tags = vec!["loop".to_string(), "slow".to_string()];
+
+        let joined = concat(&[a, b], 500);
+        assert_eq!(joined.actions.len(), 22);
+        assert_eq!(joined.actions[11].at, 1500);
+        assert_eq!(joined.actions[21].at, 2500);
+        assert_eq!(joined.raw_actions.len(), 22);
+        assert_eq!(joined.metadata.tags, vec!["loop", "slow"]);
+        // the first part has no duration so neither does the result
+        assert_eq!(joined.metadata.duration, -1);
+
+        let chapters: Vec<_> = joined
+            .metadata
+            .chapters()
+            .iter()
+            .map(|c| (c.name.as_str(), c.start_time, c.end_time))
+            .collect();
+        assert_eq!(chapters, vec![("first", 0, 1000), ("part 2", 1500, 3500)]);
+        assert!(concat(&[], 100).actions.is_empty());
+    }
+
+    #[test]
+    fn test_splice() {
+        let mut target = full_range();
+        target.metadata.bookmarks = vec![100, 700];
+        target.metadata.chapters_mut().push(Chapter {
+            name: "all".to_string(),
+            start_time: 0,
+            end_time: 1000,
+        });
+        let mut insert = FScript::default();
+        insert.actions.push(FSPoint { at: 0, pos: 90 });
+        insert.actions.push(FSPoint { at: 200, pos: 10 });
+
+        splice(&mut target, &insert, 450);
+        let points: Vec<_> = target.actions[3..9]
+            .iter()
+            .map(|pt| (pt.at, pt.pos))
+            .collect();
+        assert_eq!(
+            points,
+            vec![
+                (300, 30),
+                (400, 40),
+                (450, 90),
+                (650, 10),
+                (700, 50),
+                (800, 60)
+            ]
+        );
+        assert!(target.actions.windows(2).all(|w| w[0].at < w[1].at));
+        assert_eq!(target.actions.last().unwrap().at, 1200);
+        assert_eq!(target.metadata.bookmarks, vec![100, 900]);
+        assert_eq!(target.metadata.chapters()[0].end_time, 1200);
+        // the insert has no raw actions, the raw actions are only split
+        assert_eq!(target.raw_actions[5].at, 450);
+        assert_eq!(target.raw_actions[6].at, 650);
+    }
+
+    #[test]
+    fn test_invert() {
+        let mut s = full_range();
+        invert(&mut s);
+        assert_eq!(s.actions[0].pos, 100);
+        assert_eq!(s.raw_actions[3].pos, 70);
+        assert!(!s.inverted);
+
+        s.inverted = true;
+        let resolved = s.resolved_actions();
+        assert_eq!(resolved[0].pos, 0);
+        assert_eq!(resolved[3].at, 300);
+        assert_eq!(resolved[3].pos, 30);
+    }
+
+    #[test]
+    fn test_normalize_range() {
+        let mut s = FScript::default();
+        for (at, pos) in [(0, 30), (100, 70), (200, 50)] {
+            s.actions.push(FSPoint { at, pos });
+        }
+        normalize_range(&mut s, true);
+        let positions: Vec<_> = s.actions.iter().map(|pt| pt.pos).collect();
+        assert_eq!(positions, vec![0, 100, 50]);
+
+        s.actions[1].pos = 150;
+        normalize_range(&mut s, true);
+        assert_eq!(s.actions[1].pos, 150);
+        normalize_range(&mut s, false);
+        let positions: Vec<_> = s.actions.iter().map(|pt| pt.pos).collect();
+        assert_eq!(positions, vec![0, 100, 33]);
+    }
+
+    #[test]
+    fn test_scale_amplitude() {
+        let mut s = full_range();
+        let report = scale_amplitude(&mut s, 0.5, 50.0);
+        assert_eq!(report, OffsetReport::default());
+        assert_eq!(s.actions[0].pos, 25);
+        assert_eq!(s.actions[10].pos, 75);
+        assert_eq!(s.raw_actions[0].pos, 0);
+
+        let report = scale_amplitude(&mut s, 3.0, 25.0);
+        assert_eq!(s.actions[0].pos, 25);
+        assert_eq!(s.actions[2].pos, 55);
+        assert_eq!(report.clamped_high, 5);
+    }
+
+    #[test]
+    fn test_fill_gaps() {
+        let gappy = || {
+            let mut s = FScript::default();
+            for (at, pos) in [(0, 0), (1000, 100), (11000, 100), (11500, 0), (41500, 0)] {
+                s.actions.push(FSPoint { at, pos });
+            }
+            s
+        };
+        let options = FillOptions {
+            max_gap_ms: Some(20000),
+            ..Default::default()
+        };
+
+        let mut s = gappy();
+        assert_eq!(fill_gaps(&mut s, FillPattern::Hold, options), 1);
+        assert_eq!((s.actions[2].at, s.actions[2].pos), (10500, 100));
+        assert_eq!(s.actions.len(), 6);
+
+        let mut s = gappy();
+        fill_gaps(&mut s, FillPattern::Fade { rest: 30 }, options);
+        let points: Vec<_> = s.actions[2..4].iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(points, vec![(3750, 30), (8250, 30)]);
+
+        let mut s = gappy();
+        let wave = FillPattern::SlowWave {
+            period_ms: 4000,
+            amplitude: 40,
+        };
+        assert_eq!(fill_gaps(&mut s, wave, FillOptions::default()), 2);
+        // 1500..=10500 every 2s, starting down because the gap starts at the top
+        let first_gap: Vec<_> = s.actions[2..7].iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(
+            first_gap,
+            vec![(1500, 30), (3500, 70), (5500, 30), (7500, 70), (9500, 30)]
+        );
+        assert!(s.actions.windows(2).all(|w| w[0].at < w[1].at));
+
+        // a long slow ramp is movement, not a gap
+        let mut s = FScript::default();
+        for (at, pos) in [(0, 50), (1000, 100), (11000, 0), (12000, 50)] {
+            s.actions.push(FSPoint { at, pos });
+        }
+        let before = s.clone();
+        assert_eq!(fill_gaps(&mut s, FillPattern::Hold, options), 0);
+        assert_eq!(fill_gaps(&mut s, wave, options), 0);
+        assert_eq!(s, before);
+
+        // a position held over several points is one gap
+        let mut s = FScript::default();
+        for (at, pos) in [(0, 0), (1000, 100), (4000, 100), (11000, 100), (12000, 0)] {
+            s.actions.push(FSPoint { at, pos });
+        }
+        assert_eq!(
+            fill_gaps(&mut s, FillPattern::Fade { rest: 30 }, options),
+            1
+        );
+        let points: Vec<_> = s.actions[1..5].iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(
+            points,
+            vec![(1000, 100), (3750, 30), (8250, 30), (11000, 100)]
+        );
+    }
+
+    #[test]
+    fn test_merge_overlay() {
+        let script = |points: &[(i32, i32)]| {
+            let mut s = FScript::default();
+            for &(at, pos) in points {
+                s.actions.push(FSPoint { at, pos });
+            }
+            s
+        };
+        let a = script(&[(0, 0), (200, 100), (400, 0)]);
+        let b = script(&[(100, 100), (300, 100), (600, 40)]);
+        let merged = |strategy| {
+            let out = merge_overlay(&[a.clone(), b.clone()], strategy);
+            out.actions
+                .iter()
+                .map(|pt| (pt.at, pt.pos))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            merged(MergeStrategy::Average),
+            vec![
+                (0, 0),
+                (100, 75),
+                (200, 100),
+                (300, 75),
+                (400, 40),
+                (600, 40)
+            ]
+        );
+        assert_eq!(
+            merged(MergeStrategy::Max),
+            vec![
+                (0, 0),
+                (100, 100),
+                (200, 100),
+                (300, 100),
+                (400, 80),
+                (600, 40)
+            ]
+        );
+        assert_eq!(
+            merged(MergeStrategy::Priority),
+            vec![
+                (0, 0),
+                (100, 50),
+                (200, 100),
+                (300, 50),
+                (400, 0),
+                (600, 40)
+            ]
+        );
+        assert!(merge_overlay(&[], MergeStrategy::Max).actions.is_empty());
+    }
+
+    #[test]
+    fn test_split_by_chapters() {
+        let mut s = full_range();
+        s.metadata.title = "scene".to_string();
+        s.metadata.chapters_mut().extend([
+            Chapter {
+                name: "intro".to_string(),
+                start_time: 0,
+                end_time: 300,
+            },
+            Chapter {
+                name: "main".to_string(),
+                start_time: 300,
+                end_time: 1000,
+            },
+        ]);
+
+        let parts = split_by_chapters(&s);
+        assert_eq!(parts.len(), 2);
+        let (chapter, main) = &parts[1];
+        assert_eq!(chapter.name, "main");
+        assert_eq!(main.metadata.title, "scene - main");
+        assert_eq!(main.actions.first().unwrap().at, 0);
+        assert_eq!(main.actions.last().unwrap().at, 700);
+        assert_eq!(main.metadata.chapters().len(), 1);
+        assert!(split_by_chapters(&full_range()).is_empty());
+    }
+
+    #[test]
+    fn test_split_at() {
+        let s = full_range();
+        let parts = split_at(&s, &[700, 250, -5, 5000]);
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].metadata.title, "part 1");
+        let spans: Vec<_> = parts
+            .iter()
+            .map(|p| (p.actions.first().unwrap().pos, p.actions.last().unwrap().at))
+            .collect();
+        assert_eq!(spans, vec![(0, 250), (25, 450), (70, 300)]);
+
+        let halves = split_equal(&s, 2);
+        assert_eq!(halves.len(), 2);
+        assert_eq!(halves[1].actions.first().unwrap().pos, 50);
+        assert_eq!(split_equal(&s, 0).len(), 1);
+    }
+
+    #[test]
+    fn test_crossfade() {
+        let mut a = FScript::default();
+        let mut b = FScript::default();
+        for i in 0..=10 {
+            a.actions.push(FSPoint {
+                at: i * 100,
+                pos: 0,
+            });
+            b.actions.push(FSPoint {
+                at: i * 100,
+                pos: 100,
+            });
+        }
+
+        let faded = crossfade(&a, &b, 400);
+        assert_eq!(faded.actions.first().unwrap().at, 0);
+        assert_eq!(faded.actions.last().unwrap().at, 1600);
+        assert!(faded.actions.windows(2).all(|w| w[0].at < w[1].at));
+        let overlap: Vec<_> = faded
+            .actions
+            .iter()
+            .filter(|pt| (600..=1000).contains(&pt.at))
+            .map(|pt| pt.pos)
+            .collect();
+        assert_eq!(overlap, vec![0, 25, 50, 75, 100]);
+        assert_eq!(faded.metadata.chapters()[1].start_time, 600);
+
+        // without an overlap `b` takes over right at the end of `a`
+        let joined = crossfade(&a, &b, 0);
+        assert_eq!(joined.actions.len(), 21);
+        assert_eq!((joined.actions[10].at, joined.actions[10].pos), (1000, 100));
+    }
+
+    #[test]
+    fn test_time_scale() {
+        let mut s = full_range();
+        s.metadata.duration = 3;
+        s.metadata.bookmarks = vec![500];
+        s.metadata.chapters_mut().push(Chapter {
+            name: "all".to_string(),
+            start_time: 100,
+            end_time: 1000,
+        });
+        time_scale(&mut s, 1.5);
+        assert_eq!(s.actions[3].at, 450);
+        assert_eq!(s.raw_actions[10].at, 1500);
+        assert_eq!(s.metadata.bookmarks, vec![750]);
+        assert_eq!(s.metadata.chapters()[0].end_time, 1500);
+        assert_eq!(s.metadata.duration, 5);
+
+        time_scale(&mut s, 0.001);
+        let points: Vec<_> = s.actions.iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(points, vec![(0, 30), (1, 90), (2, 100)]);
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut s = full_range();
+        s.metadata.bookmarks = vec![100, 700];
+        s.metadata.chapters_mut().extend([
+            Chapter {
+                name: "a".to_string(),
+                start_time: 0,
+                end_time: 400,
+            },
+            Chapter {
+                name: "b".to_string(),
+                start_time: 400,
+                end_time: 1000,
+            },
+        ]);
+        reverse(&mut s);
+        assert_eq!((s.actions[0].at, s.actions[0].pos), (0, 100));
+        assert_eq!((s.actions[10].at, s.actions[10].pos), (1000, 0));
+        assert_eq!(s.raw_actions[2].pos, 80);
+        assert_eq!(s.metadata.bookmarks, vec![300, 900]);
+        let chapters: Vec<_> = s
+            .metadata
+            .chapters()
+            .iter()
+            .map(|c| (c.name.as_str(), c.start_time, c.end_time))
+            .collect();
+        assert_eq!(chapters, vec![("b", 0, 600), ("a", 600, 1000)]);
+
+        // a longer video keeps the script at the same distance from the end
+        let mut s = full_range();
+        s.metadata.duration = 2;
+        reverse(&mut s);
+        assert_eq!(s.actions[0].at, 1000);
+    }
+
+    #[test]
+    fn test_loop_section() {
+        let mut s = full_range();
+        s.metadata.bookmarks = vec![900];
+        loop_section(&mut s, 200, 400, 3);
+        let points: Vec<_> = s.actions[1..9].iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(
+            points,
+            vec![
+                (100, 10),
+                (200, 20),
+                (300, 30),
+                (400, 40),
+                (500, 30),
+                (600, 40),
+                (700, 30),
+                (800, 40)
+            ]
+        );
+        assert_eq!(s.actions.last().unwrap().at, 1400);
+        assert_eq!(s.raw_actions.len(), s.actions.len());
+        assert_eq!(s.metadata.bookmarks, vec![1300]);
+
+        let mut s = full_range();
+        loop_section(&mut s, 200, 400, 1);
+        assert_eq!(s.actions.len(), 11);
+    }
+
+    #[test]
+    fn test_offset_positions_zero() {
+        let mut s = full_range();
+        let report = offset_positions(&mut s, 0, true);
+        assert_eq!(report, OffsetReport::default());
+        assert!(s
+            .actions
+            .iter()
+            .enumerate()
+            .all(|(i, pt)| pt.pos == i as i32 * 10));
+    }
+}
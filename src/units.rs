@@ -0,0 +1,309 @@
+use std::time::Duration;
+
+use crate::transform::source_length;
+use crate::{insert_pt, FSPoint, FScript, FunscriptError};
+
+/// a position that is known to be within 0-100
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Position(u8);
+
+impl Position {
+    pub const MIN: Position = Position(0);
+    pub const MAX: Position = Position(100);
+
+    /// `None` if `value` is outside 0-100
+    pub fn new(value: i32) -> Option<Position> {
+        (0..=100).contains(&value).then_some(Position(value as u8))
+    }
+
+    /// clamps `value` into 0-100
+    pub fn saturating(value: i32) -> Position {
+        Position(value.clamp(0, 100) as u8)
+    }
+
+    pub fn get(self) -> u8 {
+        self.0
+    }
+
+    /// the position seen from the other end of the stroke
+    pub fn inverted(self) -> Position {
+        Position(100 - self.0)
+    }
+}
+
+impl TryFrom<i32> for Position {
+    type Error = FunscriptError;
+
+    /// fails with [`FunscriptError::OutOfRange`] outside 0-100
+    fn try_from(value: i32) -> Result<Position, FunscriptError> {
+        Position::new(value).ok_or(FunscriptError::OutOfRange {
+            index: 0,
+            field: "pos",
+            value: value as i64,
+        })
+    }
+}
+
+impl TryFrom<u8> for Position {
+    type Error = FunscriptError;
+
+    /// fails with [`FunscriptError::OutOfRange`] above 100
+    fn try_from(value: u8) -> Result<Position, FunscriptError> {
+        Position::try_from(value as i32)
+    }
+}
+
+impl From<Position> for u8 {
+    fn from(pos: Position) -> u8 {
+        pos.0
+    }
+}
+
+impl From<Position> for i32 {
+    fn from(pos: Position) -> i32 {
+        pos.0 as i32
+    }
+}
+
+/// a script time in milliseconds, negative before the video starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Timestamp(pub i64);
+
+impl Timestamp {
+    pub fn millis(self) -> i64 {
+        self.0
+    }
+
+    /// the time as a [`Duration`], `None` for negative times
+    pub fn to_duration(self) -> Option<Duration> {
+        u64::try_from(self.0).ok().map(Duration::from_millis)
+    }
+
+    /// the nearest time an action point can hold
+    fn to_at(self) -> i32 {
+        self.0.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+}
+
+impl From<i64> for Timestamp {
+    fn from(ms: i64) -> Timestamp {
+        Timestamp(ms)
+    }
+}
+
+impl From<i32> for Timestamp {
+    fn from(ms: i32) -> Timestamp {
+        Timestamp(ms as i64)
+    }
+}
+
+impl From<Timestamp> for i64 {
+    fn from(t: Timestamp) -> i64 {
+        t.0
+    }
+}
+
+impl From<Duration> for Timestamp {
+    /// saturates at `i64::MAX` milliseconds
+    fn from(d: Duration) -> Timestamp {
+        Timestamp(i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+    }
+}
+
+impl FSPoint {
+    /// a point from checked values, times beyond the i32 range are clamped
+    pub fn new(at: Timestamp, pos: Position) -> FSPoint {
+        FSPoint {
+            at: at.to_at(),
+            pos: pos.into(),
+        }
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        self.at.into()
+    }
+
+    /// the position, `None` if the point holds a value outside 0-100
+    pub fn position(&self) -> Option<Position> {
+        Position::new(self.pos)
+    }
+
+    /// a point at a time given as a [`Duration`], clamped to the i32 millisecond range
+    pub fn from_duration(at: Duration, pos: i32) -> FSPoint {
+        FSPoint {
+            at: Timestamp::from(at).to_at(),
+            pos,
+        }
+    }
+
+    /// the time of the point as a [`Duration`], zero for points before the video starts
+    pub fn at_duration(&self) -> Duration {
+        self.timestamp().to_duration().unwrap_or_default()
+    }
+}
+
+impl FScript {
+    /// length of the script, the video duration from the metadata or the time of the
+    /// last action if that is later
+    pub fn duration(&self) -> Duration {
+        Timestamp(source_length(self))
+            .to_duration()
+            .unwrap_or_default()
+    }
+
+    /// the actions as checked values
+    /// fails on the first point with a negative time or a position outside 0-100
+    pub fn typed_actions(&self) -> Result<Vec<(Timestamp, Position)>, FunscriptError> {
+        self.actions
+            .iter()
+            .enumerate()
+            .map(|(index, pt)| {
+                if pt.at < 0 {
+                    return Err(FunscriptError::OutOfRange {
+                        index,
+                        field: "at",
+                        value: pt.at as i64,
+                    });
+                }
+                let pos = pt.position().ok_or(FunscriptError::OutOfRange {
+                    index,
+                    field: "pos",
+                    value: pt.pos as i64,
+                })?;
+                Ok((pt.timestamp(), pos))
+            })
+            .collect()
+    }
+
+    /// adds a point from checked values, keeping the actions sorted
+    /// an existing point at the same time is overwritten, returns its index
+    pub fn insert_typed(&mut self, at: Timestamp, pos: Position) -> usize {
+        let pt = FSPoint::new(at, pos);
+        insert_pt(self, pt.at, pt.pos)
+    }
+}
+
+/// the time in ms at which `frame` starts, rounded to the nearest ms
+pub fn frame_to_ms(frame: i64, fps: f64) -> i64 {
+    (frame as f64 * 1000.0 / fps).round() as i64
+}
+
+/// the frame showing at `ms`
+pub fn ms_to_frame(ms: i64, fps: f64) -> i64 {
+    // half a ms of slack so frame_to_ms(n) maps back to n despite its rounding
+    ((ms as f64 + 0.5) * fps / 1000.0).floor() as i64
+}
+
+impl FScript {
+    /// moves every action to the start of the nearest frame
+    /// actions that land on the same frame are merged, the later one wins,
+    /// returns how many actions were merged away
+    pub fn snap_to_frames(&mut self, fps: f64) -> usize {
+        if fps.is_nan() || fps <= 0.0 {
+            return 0;
+        }
+        let before = self.actions.len();
+        for pt in &mut self.actions {
+            let frame = (pt.at as f64 * fps / 1000.0).round() as i64;
+            pt.at = Timestamp(frame_to_ms(frame, fps)).to_at();
+        }
+        // dedup_by keeps the first of a run, swap so the later action survives
+        self.actions.dedup_by(|later, earlier| {
+            let same = later.at == earlier.at;
+            if same {
+                std::mem::swap(later, earlier);
+            }
+            same
+        });
+        before - self.actions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_and_timestamp() {
+        assert_eq!(Position::new(101), None);
+        assert_eq!(Position::new(-1), None);
+        assert_eq!(Position::new(40).unwrap().get(), 40);
+        assert_eq!(Position::saturating(250), Position::MAX);
+        assert_eq!(Position::new(30).unwrap().inverted().get(), 70);
+        assert_eq!(Position::try_from(100i32).unwrap(), Position::MAX);
+        assert_eq!(Position::try_from(0u8).unwrap(), Position::MIN);
+        assert!(matches!(
+            Position::try_from(-3i32),
+            Err(FunscriptError::OutOfRange { value: -3, .. })
+        ));
+        assert!(matches!(
+            Position::try_from(101u8),
+            Err(FunscriptError::OutOfRange { value: 101, .. })
+        ));
+
+        let t = Timestamp::from(Duration::from_millis(1500));
+        assert_eq!(t.millis(), 1500);
+        assert_eq!(t.to_duration(), Some(Duration::from_secs_f64(1.5)));
+        assert_eq!(Timestamp(-20).to_duration(), None);
+        assert_eq!(
+            FSPoint::new(Timestamp(i64::MAX), Position::MIN).at,
+            i32::MAX
+        );
+    }
+
+    #[test]
+    fn test_duration_helpers() {
+        let s = FScript::builder()
+            .push_action_at(Duration::from_millis(250), 0)
+            .push_action_at(Duration::from_secs(2), 100)
+            .build()
+            .unwrap();
+        assert_eq!(s.actions[0].at, 250);
+        assert_eq!(s.actions[1].at_duration(), Duration::from_secs(2));
+        assert_eq!(s.duration(), Duration::from_secs(2));
+        assert_eq!(FSPoint { at: -5, pos: 0 }.at_duration(), Duration::ZERO);
+
+        let ofs = crate::load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        assert_eq!(ofs.duration(), Duration::from_secs(2610));
+        assert_eq!(FScript::default().duration(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_frames() {
+        assert_eq!(frame_to_ms(30, 30.0), 1000);
+        assert_eq!(frame_to_ms(1, 29.97), 33);
+        assert_eq!(ms_to_frame(1000, 30.0), 30);
+        assert_eq!(ms_to_frame(999, 30.0), 29);
+        for frame in 0..10_000 {
+            assert_eq!(ms_to_frame(frame_to_ms(frame, 59.94), 59.94), frame);
+        }
+
+        let mut s = FScript::default();
+        for (at, pos) in [(0, 0), (20, 10), (30, 20), (110, 100)] {
+            s.actions.push(FSPoint { at, pos });
+        }
+        assert_eq!(s.snap_to_frames(30.0), 1);
+        let points: Vec<_> = s.actions.iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(points, vec![(0, 0), (33, 20), (100, 100)]);
+        assert_eq!(s.snap_to_frames(0.0), 0);
+    }
+
+    #[test]
+    fn test_typed_actions() {
+        let mut s = FScript::default();
+        s.insert_typed(Timestamp(200), Position::MAX);
+        s.insert_typed(Timestamp(0), Position::MIN);
+        let typed = s.typed_actions().unwrap();
+        assert_eq!(typed[1], (Timestamp(200), Position::MAX));
+
+        s.actions.push(FSPoint { at: 300, pos: 120 });
+        assert!(matches!(
+            s.typed_actions(),
+            Err(FunscriptError::OutOfRange {
+                index: 2,
+                field: "pos",
+                value: 120
+            })
+        ));
+    }
+}
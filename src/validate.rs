@@ -0,0 +1,341 @@
+use crate::{FSPoint, FScript};
+
+/// how serious a [`Diagnostic`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// worth knowing, players handle it fine
+    Info,
+    /// likely a mistake, some players may misbehave
+    Warning,
+    /// players will reject the script or play it wrong
+    Error,
+}
+
+/// what a [`Diagnostic`] is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    EmptyActions,
+    NonMonotonic,
+    DuplicateTime,
+    PositionOutOfRange,
+    NegativeTime,
+    DurationMismatch,
+    InvalidChapter,
+}
+
+/// a problem found by [`validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    /// index into `actions` of the offending point, if the problem is with a point
+    pub index: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(
+        severity: Severity,
+        kind: DiagnosticKind,
+        index: Option<usize>,
+        message: String,
+    ) -> Self {
+        Self {
+            severity,
+            kind,
+            index,
+            message,
+        }
+    }
+}
+
+/// checks a script for problems players and editors trip over
+/// diagnostics are returned in action order, metadata problems come last
+pub fn validate(script: &FScript) -> Vec<Diagnostic> {
+    use DiagnosticKind::*;
+    use Severity::*;
+
+    let mut out = Vec::new();
+    if script.actions.is_empty() {
+        out.push(Diagnostic::new(
+            Error,
+            EmptyActions,
+            None,
+            "script has no actions".to_string(),
+        ));
+    }
+
+    let mut prev: Option<&FSPoint> = None;
+    for (i, pt) in script.actions.iter().enumerate() {
+        if pt.at < 0 {
+            out.push(Diagnostic::new(
+                Error,
+                NegativeTime,
+                Some(i),
+                format!("action {i} has negative time {}", pt.at),
+            ));
+        }
+        if !(0..=100).contains(&pt.pos) {
+            out.push(Diagnostic::new(
+                Error,
+                PositionOutOfRange,
+                Some(i),
+                format!("action {i} has position {} outside 0-100", pt.pos),
+            ));
+        }
+        if let Some(prev) = prev {
+            if pt.at == prev.at {
+                out.push(Diagnostic::new(
+                    Warning,
+                    DuplicateTime,
+                    Some(i),
+                    format!("action {i} has the same time {} as the one before", pt.at),
+                ));
+            } else if pt.at < prev.at {
+                out.push(Diagnostic::new(
+                    Error,
+                    NonMonotonic,
+                    Some(i),
+                    format!(
+                        "action {i} at {} comes before the previous action at {}",
+                        pt.at, prev.at
+                    ),
+                ));
+            }
+        }
+        prev = Some(pt);
+    }
+
+    let meta = &script.metadata;
+    let last_at = script.actions.iter().map(|pt| pt.at).max();
+    if let Some(last_at) = last_at {
+        // metadata duration is in seconds
+        if meta.duration > 0 && last_at as i64 > meta.duration as i64 * 1000 + 1000 {
+            out.push(Diagnostic::new(
+                Warning,
+                DurationMismatch,
+                None,
+                format!(
+                    "last action at {last_at}ms is after the metadata duration of {}s",
+                    meta.duration
+                ),
+            ));
+        }
+    }
+    for chapter in meta.chapters() {
+        if chapter.end_time < chapter.start_time || chapter.start_time < 0 {
+            out.push(Diagnostic::new(
+                Warning,
+                InvalidChapter,
+                None,
+                format!(
+                    "chapter \"{}\" has an invalid range {}..{}",
+                    chapter.name, chapter.start_time, chapter.end_time
+                ),
+            ));
+        } else if meta.duration > 0 && chapter.start_time > meta.duration as i64 * 1000 {
+            out.push(Diagnostic::new(
+                Info,
+                InvalidChapter,
+                None,
+                format!(
+                    "chapter \"{}\" starts after the end of the video",
+                    chapter.name
+                ),
+            ));
+        }
+    }
+    out
+}
+
+/// which point survives when several actions share a timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    #[default]
+    KeepFirst,
+    KeepLast,
+    /// one point with the rounded mean position
+    Average,
+}
+
+/// what [`sanitize`] should fix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SanitizeOptions {
+    pub duplicates: DuplicatePolicy,
+    /// clamp positions into 0-100 instead of leaving them alone
+    pub clamp_positions: bool,
+    /// drop actions with negative times, which no player can schedule
+    pub drop_negative_times: bool,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        Self {
+            duplicates: DuplicatePolicy::default(),
+            clamp_positions: true,
+            drop_negative_times: true,
+        }
+    }
+}
+
+/// what [`sanitize`] changed
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SanitizeSummary {
+    /// the actions were out of order and have been sorted
+    pub sorted: bool,
+    /// actions removed because another action had the same time
+    pub duplicates_removed: usize,
+    pub positions_clamped: usize,
+    pub negative_times_dropped: usize,
+}
+
+/// fixes the problems [`validate`] reports for the actions
+/// sorting is stable so keep first/last refers to the order in the file
+pub fn sanitize(script: &mut FScript, options: SanitizeOptions) -> SanitizeSummary {
+    let mut summary = SanitizeSummary::default();
+    let actions = &mut script.actions;
+
+    if options.drop_negative_times {
+        let before = actions.len();
+        actions.retain(|pt| pt.at >= 0);
+        summary.negative_times_dropped = before - actions.len();
+    }
+
+    if actions.windows(2).any(|w| w[1].at < w[0].at) {
+        actions.sort_by_key(|pt| pt.at);
+        summary.sorted = true;
+    }
+
+    let before = actions.len();
+    let mut deduped: Vec<FSPoint> = Vec::with_capacity(actions.len());
+    let mut group_start = 0;
+    for i in 0..actions.len() {
+        let last_of_group = actions
+            .get(i + 1)
+            .is_none_or(|next| next.at != actions[i].at);
+        if !last_of_group {
+            continue;
+        }
+        let group = &actions[group_start..=i];
+        let pos = match options.duplicates {
+            DuplicatePolicy::KeepFirst => group[0].pos,
+            DuplicatePolicy::KeepLast => group[group.len() - 1].pos,
+            DuplicatePolicy::Average => {
+                let sum: i64 = group.iter().map(|pt| pt.pos as i64).sum();
+                (sum as f64 / group.len() as f64).round() as i32
+            }
+        };
+        deduped.push(FSPoint {
+            at: actions[i].at,
+            pos,
+        });
+        group_start = i + 1;
+    }
+    summary.duplicates_removed = before - deduped.len();
+    *actions = deduped;
+
+    if options.clamp_positions {
+        for pt in actions.iter_mut() {
+            if !(0..=100).contains(&pt.pos) {
+                pt.pos = pt.pos.clamp(0, 100);
+                summary.positions_clamped += 1;
+            }
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_funscript, Chapter};
+
+    #[test]
+    fn test_validate_clean_file() {
+        let s = load_funscript("./test-scripts/openfunscripter.funscript").unwrap();
+        let diagnostics = validate(&s);
+        assert!(
+            diagnostics.iter().all(|d| d.severity < Severity::Error),
+            "{diagnostics:?}"
+        );
+        assert!(validate(&FScript::default())
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::EmptyActions));
+    }
+
+    #[test]
+    fn test_validate_problems() {
+        let mut s = FScript::default();
+        for (at, pos) in [(0, 0), (100, 120), (100, 50), (50, 10), (-10, 0)] {
+            s.actions.push(FSPoint { at, pos });
+        }
+        s.metadata.duration = 1;
+        s.metadata.chapters_mut().push(Chapter {
+            name: "backwards".to_string(),
+            start_time: 500,
+            end_time: 100,
+        });
+
+        let found: Vec<_> = validate(&s).iter().map(|d| (d.kind, d.index)).collect();
+        use DiagnosticKind::*;
+        assert_eq!(
+            found,
+            vec![
+                (PositionOutOfRange, Some(1)),
+                (DuplicateTime, Some(2)),
+                (NonMonotonic, Some(3)),
+                (NegativeTime, Some(4)),
+                (NonMonotonic, Some(4)),
+                (InvalidChapter, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let messy = || {
+            let mut s = FScript::default();
+            for (at, pos) in [
+                (200, 10),
+                (0, 0),
+                (100, 120),
+                (100, 50),
+                (-10, 0),
+                (100, 30),
+            ] {
+                s.actions.push(FSPoint { at, pos });
+            }
+            s
+        };
+
+        let mut s = messy();
+        let summary = sanitize(&mut s, SanitizeOptions::default());
+        assert_eq!(
+            summary,
+            SanitizeSummary {
+                sorted: true,
+                duplicates_removed: 2,
+                positions_clamped: 1,
+                negative_times_dropped: 1,
+            }
+        );
+        let points: Vec<_> = s.actions.iter().map(|pt| (pt.at, pt.pos)).collect();
+        assert_eq!(points, vec![(0, 0), (100, 100), (200, 10)]);
+        assert!(validate(&s).is_empty());
+
+        for (policy, expected) in [
+            (DuplicatePolicy::KeepLast, 30),
+            (DuplicatePolicy::Average, 67),
+        ] {
+            let mut s = messy();
+            let options = SanitizeOptions {
+                duplicates: policy,
+                clamp_positions: false,
+                drop_negative_times: false,
+            };
+            sanitize(&mut s, options);
+            assert_eq!(s.actions.len(), 4);
+            assert_eq!(s.actions[2].pos, expected);
+        }
+    }
+}
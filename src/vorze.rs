@@ -0,0 +1,65 @@
+use crate::FScript;
+
+/// stroke speed in units per second that maps to full rotation speed
+pub const VORZE_FULL_SPEED: f64 = 400.0;
+
+/// the script as a Vorze A10 Cyclone csv of `time,direction,speed` lines
+/// time is in tenths of a second, the rotation direction flips with every change of
+/// stroke direction and the rotation speed follows the stroke speed, with
+/// `full_speed` units per second mapping to the device's maximum of 100
+pub fn to_vorze_csv(script: &FScript, full_speed: f64) -> String {
+    let mut lines: Vec<(i64, u8, i64)> = Vec::new();
+    let mut push = |line: (i64, u8, i64)| match lines.last_mut() {
+        // the device only has a tenth of a second resolution, the later move wins
+        Some(prev) if prev.0 == line.0 => *prev = line,
+        _ => lines.push(line),
+    };
+    let tenths = |at: i32| (at.max(0) as i64 + 50) / 100;
+
+    let mut direction = 0;
+    for seg in script.segments() {
+        match seg.delta() {
+            d if d > 0 => direction = 1,
+            d if d < 0 => direction = 0,
+            _ => {}
+        }
+        let speed = if seg.duration_ms() > 0 {
+            (seg.speed() / full_speed * 100.0).round().clamp(0.0, 100.0) as i64
+        } else {
+            0
+        };
+        push((tenths(seg.start.at), direction, speed));
+    }
+    if let Some(end) = script.actions.last() {
+        push((tenths(end.at), direction, 0));
+    }
+
+    let mut csv = String::new();
+    let mut last = None;
+    for (time, direction, speed) in lines {
+        // repeating the current state is just noise for the device
+        if last == Some((direction, speed)) {
+            continue;
+        }
+        last = Some((direction, speed));
+        csv.push_str(&format!("{time},{direction},{speed}\n"));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FSPoint;
+
+    #[test]
+    fn test_to_vorze_csv() {
+        let mut s = FScript::default();
+        for (at, pos) in [(0, 0), (500, 100), (1000, 0), (2000, 0), (2200, 80)] {
+            s.actions.push(FSPoint { at, pos });
+        }
+        let csv = to_vorze_csv(&s, VORZE_FULL_SPEED);
+        assert_eq!(csv, "0,1,50\n5,0,50\n10,0,0\n20,1,100\n22,1,0\n");
+        assert_eq!(to_vorze_csv(&FScript::default(), VORZE_FULL_SPEED), "");
+    }
+}